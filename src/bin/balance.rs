@@ -0,0 +1,24 @@
+use ggj_2k33::{
+    launch::LaunchError,
+    simulation::{run_balance, BalanceConfig, BalanceReport, BALANCE_USAGE},
+};
+
+fn main() {
+    let config = match BalanceConfig::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(LaunchError::HelpRequested) => {
+            println!("{BALANCE_USAGE}");
+            return;
+        }
+        Err(error) => {
+            eprintln!("error: {error}\n\nRun with --help to list the options.");
+            std::process::exit(2);
+        }
+    };
+    println!(
+        "Playing {} games on {} with {:?} (parents {:?})...",
+        config.games, config.difficulty, config.bot.movement, config.bot.parents
+    );
+    let runs = run_balance(&config);
+    print!("\n{}", BalanceReport::new(&runs));
+}
@@ -1,13 +1,20 @@
-use std::fmt::Display;
+use std::cmp::max;
 
 use bevy::{
     ecs::component::Component,
-    prelude::{Bundle, Color, Deref, DerefMut, ReflectComponent, Vec2},
+    prelude::{Bundle, Color, Deref, DerefMut, Gamepad, Handle, Image, ReflectComponent, Vec2},
     reflect::Reflect,
     sprite::{SpriteBundle, SpriteSheetBundle},
-    time::Timer,
+    time::{Timer, TimerMode},
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{CAMERA_FOLLOW_SPEED, CAMERA_LOOK_AHEAD, CAMERA_ZOOM_SPEED},
+    resource::{AnimationSheet, ClipName, SettingsChannel, Traits},
+    StatsRes,
 };
-use rand::{thread_rng, Rng};
 
 #[derive(Component, Default, Reflect)]
 #[reflect(Component)]
@@ -51,6 +58,15 @@ pub struct Player;
 #[reflect(Component)]
 pub struct Enemy;
 
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Elite;
+
+// Health an enemy spawned with, after its era, difficulty and elite multipliers
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct MaxHealth(pub f32);
+
 #[derive(Component, Default, Reflect)]
 #[reflect(Component)]
 pub struct Bullet;
@@ -69,8 +85,29 @@ pub struct Weapon {
     pub bullet_ttl: i32,
     pub bullets: u32,
     pub is_firing: bool,
+    // Elapsed seconds at the last shot
+    pub last_shot: f32,
+}
+
+// Input device driving a player
+#[derive(Component, Clone, Copy, PartialEq)]
+pub enum Controls {
+    // Arrows to move, aim and fire with the mouse
+    KeyboardMouse,
+    // WASD to move and space to fire, aiming where the player walks
+    Keyboard,
+    // Left stick to move, right stick to aim and right trigger to fire
+    Gamepad(Gamepad),
 }
 
+// A co-op player out of the fight until the next wave
+#[derive(Component)]
+pub struct Downed;
+
+// Index of the player in the party, the stats of the next wave are kept there
+#[derive(Component, Clone, Copy)]
+pub struct PlayerSlot(pub usize);
+
 #[derive(Bundle)]
 pub struct CharacterBundle {
     pub move_component: Move,
@@ -108,31 +145,47 @@ pub struct BulletBundle {
 #[derive(Component, Deref, DerefMut)]
 pub struct MobSpawnerTimer(pub Timer);
 
-#[derive(Component, Default, Reflect)]
+#[derive(Component, Clone, Copy, Default, Reflect)]
 #[reflect(Component)]
 pub struct MainMenu;
 
-#[derive(Component, Default, Reflect)]
+#[derive(Component, Clone, Copy, Default, Reflect)]
 #[reflect(Component)]
-pub struct InGame;
+pub struct LoadingScreen;
+
+// Fill of the loading progress bar
+#[derive(Component)]
+pub struct LoadingBar;
+
+// Lists the files that could not be loaded
+#[derive(Component)]
+pub struct LoadingText;
 
 #[derive(Component, Default, Reflect)]
 #[reflect(Component)]
-pub struct LevelMenu;
+pub struct DifficultyText;
 
 #[derive(Component, Default, Reflect)]
 #[reflect(Component)]
-pub struct DateText;
+pub struct PlayersText;
 
-#[derive(Component, Default, Reflect)]
+#[derive(Component, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct InGame;
+
+#[derive(Component, Clone, Copy, Default, Reflect)]
 #[reflect(Component)]
-pub struct LevelMenuPannel;
+pub struct LevelMenu;
 
 #[derive(Component, Default, Reflect)]
 #[reflect(Component)]
+pub struct DateText;
+
+#[derive(Component, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
 pub struct RetryMenu;
 
-#[derive(Component, Default, Reflect)]
+#[derive(Component, Clone, Copy, Default, Reflect)]
 #[reflect(Component)]
 pub struct PreStartMenu;
 
@@ -140,12 +193,141 @@ pub struct PreStartMenu;
 #[reflect(Component)]
 pub struct PlayerColor(pub Color);
 
+#[derive(Component, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct SettingsMenu;
+
+#[derive(Component, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct ModsMenu;
+
+#[derive(Component, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct AchievementsMenu;
+
+// Unlock notification, shown over whatever screen is up
+#[derive(Component, Clone, Copy)]
+pub struct Toast;
+
+// Time left before the toast holding it goes away
+#[derive(Component)]
+pub struct ToastTimer(pub Timer);
+
+#[derive(Clone, Copy)]
+pub enum SettingsAction {
+    VolumeUp(SettingsChannel),
+    VolumeDown(SettingsChannel),
+    ToggleMute(SettingsChannel),
+    Cycle(SettingsOption),
+    Back,
+}
+
+// Settings changed by cycling through their values
+#[derive(Clone, Copy)]
+pub enum SettingsOption {
+    Language,
+    Minimap,
+    DamageNumbers,
+    Palette,
+    TextScale,
+    ReduceMotion,
+    FireMode,
+}
+
+// What a button does once clicked, sent along with its `UiClickEvent`
+#[derive(Clone, Copy)]
+pub enum UiAction {
+    Start,
+    StartDaily,
+    CycleDifficulty,
+    OpenSettings,
+    OpenMods,
+    OpenAchievements,
+    // Index of the pack in the mods list
+    ToggleMod(usize),
+    EnterGame,
+    PickParent,
+    Retry,
+    CyclePlayers,
+    BackToMainMenu,
+    Settings(SettingsAction),
+}
+
+#[derive(Component)]
+pub struct UiButton {
+    pub action: UiAction,
+    // Drawn greyed out and sends no click
+    pub disabled: bool,
+}
+
+// The button keyboard and gamepad input act on
+#[derive(Component)]
+pub struct Focused;
+
+// Sent as a click on the entity holding it when the player goes back with Escape or B
+#[derive(Component)]
+pub struct UiBackAction(pub UiAction);
+
+// Font sizes a text was spawned with, scaled by the text size setting
+#[derive(Component)]
+pub struct BaseFontSize(pub Vec<f32>);
+
+// Slides down from its starting top offset until it reaches its place
+#[derive(Component)]
+pub struct SlideIn {
+    pub speed: f32,
+}
+
+#[derive(Component)]
+pub struct SettingsValueText(pub SettingsChannel);
+
+// Enabled state of a mod pack, by index in the mods list
+#[derive(Component)]
+pub struct ModToggleText(pub usize);
+
+// Shown once the enabled packs differ from the ones loaded
+#[derive(Component)]
+pub struct ModsRestartText;
+
+#[derive(Component)]
+pub struct SettingsOptionText(pub SettingsOption);
+
+// Text showing a single string, rewritten when the language changes
+#[derive(Component)]
+pub struct LocalizedText(pub &'static str);
+
+// A line of a story beat, ready to be shown in the current language
+pub struct SpokenLine {
+    pub speaker: String,
+    pub portrait: Option<Handle<Image>>,
+    pub text: String,
+}
+
+// Dialogue box playing its lines one after the other, each typed out letter by letter
+#[derive(Component)]
+pub struct Dialogue {
+    pub lines: Vec<SpokenLine>,
+    pub current: usize,
+    // Letters of the current line typed so far
+    pub shown: f32,
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum DialogueText {
+    Speaker,
+    Line,
+}
+
+#[derive(Component)]
+pub struct DialoguePortrait;
+
 #[derive(Component, Clone)]
 pub struct Debuff {
     debuff_choice: [DebufChoices; 3],
 }
 
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum DebufChoices {
     Speed,
     Bullets,
@@ -160,6 +342,33 @@ impl Debuff {
         &self.debuff_choice
     }
 
+    pub fn contains(&self, choice: DebufChoices) -> bool {
+        self.debuff_choice.contains(&choice)
+    }
+
+    // Weaken the player stats with every flaw inherited from this parent
+    pub fn apply(&self, stats: &mut StatsRes, traits: &Traits) {
+        for &flaw in &self.debuff_choice {
+            let factor = traits.factor(flaw);
+            match flaw {
+                DebufChoices::Speed => stats.player_speed *= factor,
+                DebufChoices::Bullets => {
+                    stats.player_bullets = max((stats.player_bullets as f32 * factor) as u32, 1)
+                }
+                DebufChoices::BulletsTtl => {
+                    stats.player_bullets_ttl =
+                        max((stats.player_bullets_ttl as f32 * factor) as i32, 1)
+                }
+                DebufChoices::Damage => stats.player_damage *= factor,
+                DebufChoices::BulletsSpeed => {
+                    stats.player_bullets_speed *= factor;
+                    stats.player_decay *= factor;
+                }
+                DebufChoices::FireRate => stats.player_fire_rate *= factor,
+            }
+        }
+    }
+
     fn get_default(i: usize) -> DebufChoices {
         match i {
             0 => DebufChoices::Speed,
@@ -172,14 +381,14 @@ impl Debuff {
         }
     }
 
-    pub fn get_parent_random() -> (Self, Self) {
+    pub fn get_parent_random(rng: &mut impl Rng) -> (Self, Self) {
         let mut vec_mom: Vec<usize> = vec![1, 2, 3, 4, 5, 6];
         let mut vec_dad: Vec<usize> = vec![];
-        let i1 = thread_rng().gen_range(0..6);
+        let i1 = rng.gen_range(0..6);
         vec_dad.push(vec_mom.remove(i1));
-        let i1 = thread_rng().gen_range(0..5);
+        let i1 = rng.gen_range(0..5);
         vec_dad.push(vec_mom.remove(i1));
-        let i1 = thread_rng().gen_range(0..4);
+        let i1 = rng.gen_range(0..4);
         vec_dad.push(vec_mom.remove(i1));
 
         let dad_default: [DebufChoices; 3] = [
@@ -203,32 +412,120 @@ impl Debuff {
     }
 }
 
-impl Display for Debuff {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}\n{}\n{}\n",
-            self.debuff_choice[0], self.debuff_choice[1], self.debuff_choice[2]
-        )
+impl DebufChoices {
+    pub fn key(&self) -> &'static str {
+        match self {
+            DebufChoices::Speed => "flaw.speed",
+            DebufChoices::Bullets => "flaw.bullets",
+            DebufChoices::BulletsTtl => "flaw.piercing",
+            DebufChoices::Damage => "flaw.damage",
+            DebufChoices::BulletsSpeed => "flaw.bullets_speed",
+            DebufChoices::FireRate => "flaw.fire_rate",
+        }
     }
 }
 
-impl Display for DebufChoices {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            DebufChoices::Speed => write!(f, "Player speed: -20% "),
-            DebufChoices::Bullets => write!(f, "Bullets: -50%"),
-            DebufChoices::BulletsTtl => write!(f, "Piercing shot: -50%"),
-            DebufChoices::Damage => write!(f, "Damage: -30%"),
-            DebufChoices::BulletsSpeed => write!(f, "Bullets speed/range: -40%"),
-            DebufChoices::FireRate => write!(f, "Fire rate : -30%"),
+// Clip played by an animated sprite, picked from its movement and health
+#[derive(Component)]
+pub struct Animation {
+    pub sheet: AnimationSheet,
+    pub clip: ClipName,
+    // Frame within the clip
+    pub frame: usize,
+    pub timer: Timer,
+    // Health on the last update, any drop plays the hurt clip
+    pub last_health: f32,
+}
+
+impl Animation {
+    pub fn new(sheet: AnimationSheet, health: f32) -> Self {
+        Self {
+            sheet,
+            clip: ClipName::Idle,
+            frame: 0,
+            timer: Timer::from_seconds(0.1, TimerMode::Repeating),
+            last_health: health,
         }
     }
 }
 
-#[derive(Component, Deref, DerefMut)]
-pub struct AnimationTimer(pub Timer);
+// Short lived sprite thrown by a combat effect
+#[derive(Component)]
+pub struct Particle {
+    pub age: f32,
+    pub lifetime: f32,
+    pub velocity: Vec2,
+    pub start_color: Color,
+    pub end_color: Color,
+    // Size at birth, half way through and at the end of the life
+    pub sizes: [f32; 3],
+}
+
+// Combat text rising from where it was spawned while fading out
+#[derive(Component)]
+pub struct FloatingText {
+    pub age: f32,
+    pub lifetime: f32,
+    pub speed: f32,
+    pub color: Color,
+}
+
+// Screen edge arrow pointing at the off-screen enemies of one direction
+#[derive(Component)]
+pub struct EnemyIndicator(pub usize);
+
+#[derive(Component)]
+pub struct EnemyIndicatorArrow(pub usize);
+
+#[derive(Component)]
+pub struct EnemyIndicatorText(pub usize);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MinimapLayer {
+    Chunk,
+    Enemies,
+    Player,
+}
+
+// Pooled minimap marker, the n-th one of its layer
+#[derive(Component)]
+pub struct MinimapDot {
+    pub layer: MinimapLayer,
+    pub index: usize,
+}
 
 #[derive(Component, Default, Reflect)]
 #[reflect(Component)]
 pub struct Chunk;
+
+// The camera the game world is seen through, as opposed to the menu cameras
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct MainCamera;
+
+// Steers the main camera: follows the players, shakes on impacts and zooms out in crowds
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct CameraDirector {
+    // Fraction of the distance to its target the camera covers every second
+    pub follow_speed: f32,
+    // How far ahead of the players the camera looks in their aim direction
+    pub look_ahead: f32,
+    pub zoom_speed: f32,
+    // From 0.0 to 1.0, the shake grows with its square and it wears off over time
+    pub trauma: f32,
+    // Point followed by the camera before the shake is added
+    pub focus: Vec2,
+}
+
+impl Default for CameraDirector {
+    fn default() -> Self {
+        Self {
+            follow_speed: CAMERA_FOLLOW_SPEED,
+            look_ahead: CAMERA_LOOK_AHEAD,
+            zoom_speed: CAMERA_ZOOM_SPEED,
+            trauma: 0.0,
+            focus: Vec2::ZERO,
+        }
+    }
+}
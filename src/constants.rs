@@ -1,8 +1,5 @@
 use bevy::prelude::{Color, Vec2, Vec3};
 
-//
-pub const LORE_PARENT_CHOICE: &str = "The robots of the future failed to kill you !!\nThey decided to kill one of your parents before your procreation to erase you from reality.\n";
-pub const LORE_INTRO: &str = "It is a beautiful day when suddenly an army of robots from the future appears !\nIt must probably be one of your clumsy future descendant who mess up the future !!\nDefend yourself, the destiny of your descendants depend on it !!";
 // Player starting statscargo
 pub const PLAYER_DIRECTION: Vec2 = Vec2 { x: 1.0, y: 1.0 };
 pub const PLAYER_SPEED: f32 = 400.0;
@@ -28,11 +25,24 @@ pub const BULLETS_DECAYS: f32 = 0.01;
 // Mob starting stats
 pub const MOB_COLOR: Color = Color::rgb(0.8, 0.8, 0.8);
 pub const MOB_COLOR_HURT: Color = Color::rgb(0.8, 0.0, 0.0);
+// Hurt colors and parent tints of the colorblind palettes, picked from the Okabe-Ito colors
+pub const MOB_COLOR_HURT_RED_GREEN: Color = Color::rgb(0.0, 0.45, 0.7);
+pub const MOB_COLOR_HURT_BLUE_YELLOW: Color = Color::rgb(0.8, 0.0, 0.45);
+pub const PARENT_COLORS_RED_GREEN: (Color, Color) =
+    (Color::rgb(0.9, 0.6, 0.0), Color::rgb(0.35, 0.7, 0.9));
+pub const PARENT_COLORS_BLUE_YELLOW: (Color, Color) =
+    (Color::rgb(0.84, 0.37, 0.0), Color::rgb(0.0, 0.62, 0.45));
 pub const MOB_SPEED: f32 = 180.0;
 pub const MOB_SPAWN_RADIUS: f32 = 700.0;
 pub const MOB_DAMAGE: f32 = 1.0;
 pub const MOB_HEALTH: f32 = 1.0;
 pub const MOB_SCALE: Vec3 = Vec3::new(1.0, 1.0, 1.0);
+pub const MOB_ROBOT_RATIO: f32 = 0.25;
+
+// Elite mob stats, relative to a regular mob
+pub const ELITE_HEALTH_MULTIPLIER: f32 = 4.0;
+pub const ELITE_SPEED_MULTIPLIER: f32 = 0.85;
+pub const ELITE_SCALE: Vec3 = Vec3::new(1.6, 1.6, 1.0);
 
 //Spawning
 
@@ -42,9 +52,166 @@ pub const SPEED_SPAWN_BY_LVL: [f32; 10] =
     [0.20, 0.46, 0.46, 0.46, 0.46, 0.46, 0.46, 0.46, 0.46, 0.46];
 pub const SPAWN_TICK_BY_LVL: [u32; 10] = [10, 1, 2, 2, 2, 3, 3, 3, 3, 4];
 
+// Endless mode, after the calibration tables
+pub const ENDLESS_ENEMY_GROWTH: f32 = 0.15;
+pub const ENDLESS_DENSITY_GROWTH: f32 = 0.04;
+pub const ENDLESS_MIN_SPAWN_DELAY: f32 = 0.1;
+pub const ENDLESS_BURST_GROWTH: f32 = 0.25;
+pub const ENDLESS_MAX_ROBOT_RATIO: f32 = 0.75;
+pub const ENDLESS_ROBOT_RAMP: f32 = 30.0;
+pub const ENDLESS_MAX_ELITE_RATIO: f32 = 0.2;
+pub const ENDLESS_ELITE_RAMP: f32 = 40.0;
+
 // Score parameter
 pub const BEGIN_DATE: i32 = 2100;
 pub const DECREMENT_DATE_PER_LEVEL: i32 = 35;
 
+// Historic periods, one definition file each
+pub const ERAS_FOLDER: &str = "assets/eras";
+
+// Mods, one folder per content pack with its manifest at the root
+pub const MODS_FOLDER: &str = "mods";
+pub const MOD_MANIFEST: &str = "pack.ron";
+// Files a pack names are looked up in the pack first, then in the shipped assets
+pub const ASSETS_FOLDER: &str = "assets";
+
+// Scripting, the hooks file attaches scripts to waves and flaws
+pub const SCRIPT_HOOKS: &str = "scripts/hooks.ron";
+pub const SCRIPT_RELOAD_SECONDS: f32 = 1.0;
+// Work a single handler call may do before it is stopped
+pub const SCRIPT_MAX_OPERATIONS: u64 = 100_000;
+pub const SCRIPT_MAX_SPAWNS: usize = 200;
+
+// Flaws, the factor an inherited flaw applies to the player stat
+pub const FLAW_SPEED: f32 = 0.8;
+pub const FLAW_BULLETS: f32 = 0.5;
+pub const FLAW_PIERCING: f32 = 0.5;
+pub const FLAW_DAMAGE: f32 = 0.7;
+pub const FLAW_BULLETS_SPEED: f32 = 0.6;
+// Applied to the delay between shots, so above one
+pub const FLAW_FIRE_RATE: f32 = 1.3;
+
 // Map scale
 pub const MAP_SCALE: Vec3 = Vec3::new(1.0, 1.0, 1.0);
+
+// High scores
+pub const HIGH_SCORES_FILE: &str = "high_scores.ron";
+pub const MAX_HIGH_SCORES: usize = 5;
+
+// Daily runs, a new seed every UTC day
+pub const DAILY_SCORES_FILE: &str = "daily_scores.ron";
+pub const MAX_DAILY_SCORES: usize = 7;
+pub const SECONDS_PER_DAY: u64 = 86_400;
+
+// Settings
+pub const SETTINGS_FILE: &str = "settings.ron";
+
+// Story
+pub const STORY_FILE: &str = "story.ron";
+pub const DIALOGUE_CHARS_PER_SECOND: f32 = 45.0;
+
+// Achievements
+pub const ACHIEVEMENTS_FILE: &str = "achievements.ron";
+pub const ACHIEVEMENT_TOAST_SECONDS: f32 = 4.0;
+
+// Audio
+pub const DEFAULT_VOLUME: f32 = 0.8;
+pub const VOLUME_STEP: f32 = 0.1;
+pub const MAX_SFX_VOICES: usize = 6;
+// Combat sounds fade out up to the hearing radius and are fully panned past the pan width
+pub const SPATIAL_HEARING_RADIUS: f32 = 1200.0;
+pub const SPATIAL_PAN_WIDTH: f32 = 640.0;
+pub const SPATIAL_MIN_VOLUME: f32 = 0.15;
+pub const MUSIC_CROSSFADE_SECONDS: f32 = 2.0;
+// Live enemies needed for the music to reach its top tempo
+pub const MUSIC_INTENSITY_ENEMIES: f32 = 300.0;
+pub const MUSIC_MAX_TEMPO_BOOST: f32 = 0.12;
+
+// Co-op
+// Players start this far on each side of the usual starting point
+pub const COOP_SPAWN_OFFSET: f32 = 60.0;
+pub const GAMEPAD_DEADZONE: f32 = 0.2;
+// Room kept around the players when the camera zooms out to frame them
+pub const CAMERA_FRAME_MARGIN: f32 = 200.0;
+pub const DOWNED_ALPHA: f32 = 0.3;
+
+// Camera
+pub const CAMERA_FOLLOW_SPEED: f32 = 5.5;
+pub const CAMERA_LOOK_AHEAD: f32 = 80.0;
+pub const CAMERA_ZOOM_SPEED: f32 = 2.0;
+// Trauma lost every second
+pub const CAMERA_TRAUMA_DECAY: f32 = 1.2;
+// Offset of the camera at full trauma
+pub const CAMERA_MAX_SHAKE: f32 = 20.0;
+pub const CAMERA_SHAKE_FREQUENCY: f32 = 25.0;
+pub const TRAUMA_PLAYER_HIT: f32 = 0.4;
+pub const TRAUMA_PLAYER_DOWNED: f32 = 0.7;
+pub const TRAUMA_ENEMY_DEATH: f32 = 0.03;
+// Enemies around the camera needed to reach the widest crowd zoom
+pub const CAMERA_DENSITY_ENEMIES: f32 = 400.0;
+pub const CAMERA_DENSITY_MAX_ZOOM: f32 = 1.4;
+
+// Particles
+// Live particles at most, bursts past it are cut short
+pub const PARTICLE_BUDGET: usize = 800;
+// Fraction of their speed particles lose every second
+pub const PARTICLE_DRAG: f32 = 3.0;
+// Above every character, whose depth stays below 990
+pub const PARTICLE_Z: f32 = 995.0;
+
+// Combat text
+// Health is shown in hundredths so the flaws show up as whole numbers
+pub const DAMAGE_NUMBER_SCALE: f32 = 100.0;
+pub const DAMAGE_NUMBER_BUDGET: usize = 150;
+pub const DAMAGE_NUMBER_SECONDS: f32 = 0.6;
+pub const DAMAGE_NUMBER_SPEED: f32 = 60.0;
+pub const DAMAGE_NUMBER_FONT_SIZE: f32 = 18.0;
+pub const DAMAGE_NUMBER_COLOR: Color = Color::rgb(0.95, 0.95, 0.95);
+pub const KILL_NUMBER_SECONDS: f32 = 0.9;
+pub const KILL_NUMBER_SPEED: f32 = 90.0;
+pub const KILL_NUMBER_FONT_SIZE: f32 = 28.0;
+pub const KILL_NUMBER_COLOR: Color = Color::rgb(1.0, 0.8, 0.2);
+// Above the particles
+pub const COMBAT_TEXT_Z: f32 = 998.0;
+
+// Radar
+pub const RADAR_REFRESH_SECONDS: f32 = 0.15;
+// Off-screen enemies are grouped by the direction they come from
+pub const INDICATOR_SECTORS: usize = 16;
+pub const INDICATOR_MARGIN: f32 = 36.0;
+pub const INDICATOR_ARROW_SIZE: Vec2 = Vec2::new(28.0, 8.0);
+pub const MINIMAP_SIZE: f32 = 160.0;
+// Cells on each side of the minimap, one map chunk wide
+pub const MINIMAP_CELLS: usize = 16;
+pub const MINIMAP_CELL_WORLD: f32 = 256.0;
+// Enemies in a cell for its dot to reach full size
+pub const MINIMAP_FULL_CLUSTER: f32 = 20.0;
+pub const MINIMAP_CHUNK_COLOR: Color = Color::rgba(0.55, 0.55, 0.55, 0.5);
+pub const MINIMAP_BACKGROUND_COLOR: Color = Color::rgba(0.1, 0.1, 0.1, 0.7);
+
+// UI theme
+pub const UI_FONT: &str = "fonts/FiraSans-Bold.ttf";
+pub const UI_BUTTON_COLOR: Color = Color::rgb(0.35, 0.35, 0.35);
+pub const UI_BUTTON_HOVER_COLOR: Color = Color::rgb(0.5, 0.5, 0.5);
+pub const UI_BUTTON_PRESSED_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
+pub const UI_BUTTON_FOCUS_COLOR: Color = Color::rgb(0.6, 0.5, 0.2);
+pub const UI_BUTTON_DISABLED_COLOR: Color = Color::rgba(0.35, 0.35, 0.35, 0.4);
+pub const UI_BUTTON_FONT_SIZE: f32 = 30.0;
+pub const UI_PANEL_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+pub const UI_INSET_COLOR: Color = Color::rgb(0.65, 0.65, 0.65);
+pub const UI_TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+pub const UI_SLIDE_SPEED: f32 = 100.0;
+pub const TEXT_SCALES: [f32; 4] = [0.75, 1.0, 1.25, 1.5];
+// How far the stick has to be pushed to move the focus once
+pub const UI_STICK_THRESHOLD: f32 = 0.5;
+
+// Attract mode
+pub const ATTRACT_IDLE_SECONDS: f32 = 20.0;
+pub const ATTRACT_TEXT_COLOR: Color = Color::rgb(0.9, 0.8, 0.3);
+
+// Bot
+// The kiting bot runs around the starting point at this distance
+pub const BOT_KITE_RADIUS: f32 = 300.0;
+// Seconds of game time simulated by every update of a balance run, the frame rate the game is
+// tuned for since bullets decay every frame and would skip over enemies with longer frames
+pub const SIMULATION_STEP: f32 = 1.0 / 60.0;
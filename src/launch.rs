@@ -0,0 +1,359 @@
+use std::{fmt::Display, fs, str::FromStr};
+
+use ron::extensions::Extensions;
+use serde::Deserialize;
+
+use crate::resource::Difficulty;
+
+pub const LAUNCH_USAGE: &str = "Usage: ggj_2k33 [OPTIONS]
+
+Options:
+  --config <FILE>          Read the options from a RON file, flags given after it override it
+  --resolution <WxH>       Window size, for example 1920x1080
+  --windowed               Run in a window (default)
+  --fullscreen             Run in exclusive fullscreen
+  --borderless             Run in borderless fullscreen
+  --vsync <on|off>         Synchronize frames with the display (default on)
+  --seed <NUMBER>          Seed of the run, random when not given
+  --level <NUMBER>         Wave to start from, 0 being the first one, up to 999
+  --difficulty <NAME>      story, normal, hard or nightmare
+  --headless               Run without window, straight into the game
+  --editor <on|off>        Enable the in-game editor (default on in debug builds)
+  --help                   Print this message";
+
+// Past it the dates of the waves stop meaning anything
+pub const MAX_START_LEVEL: u32 = 999;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum LaunchWindowMode {
+    Windowed,
+    Fullscreen,
+    Borderless,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LaunchConfig {
+    pub width: f32,
+    pub height: f32,
+    pub window_mode: LaunchWindowMode,
+    pub vsync: bool,
+    pub seed: Option<u64>,
+    pub start_level: u32,
+    pub difficulty: Difficulty,
+    pub headless: bool,
+    pub editor: bool,
+}
+
+impl Default for LaunchConfig {
+    fn default() -> Self {
+        Self {
+            width: 1280.,
+            height: 720.,
+            window_mode: LaunchWindowMode::Windowed,
+            vsync: true,
+            seed: None,
+            start_level: 0,
+            difficulty: Difficulty::default(),
+            headless: false,
+            editor: cfg!(debug_assertions),
+        }
+    }
+}
+
+// Every field is optional in the file, missing ones keep their current value
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LaunchConfigFile {
+    width: Option<f32>,
+    height: Option<f32>,
+    window_mode: Option<LaunchWindowMode>,
+    vsync: Option<bool>,
+    seed: Option<u64>,
+    start_level: Option<u32>,
+    difficulty: Option<Difficulty>,
+    headless: Option<bool>,
+    editor: Option<bool>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LaunchError {
+    HelpRequested,
+    UnknownFlag(String),
+    MissingValue(String),
+    InvalidValue {
+        flag: String,
+        value: String,
+        expected: &'static str,
+    },
+    ConfigFile {
+        path: String,
+        message: String,
+    },
+}
+
+impl Display for LaunchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LaunchError::HelpRequested => write!(f, "{LAUNCH_USAGE}"),
+            LaunchError::UnknownFlag(flag) => write!(f, "unknown option '{flag}'"),
+            LaunchError::MissingValue(flag) => write!(f, "option '{flag}' needs a value"),
+            LaunchError::InvalidValue {
+                flag,
+                value,
+                expected,
+            } => write!(
+                f,
+                "invalid value '{value}' for '{flag}', expected {expected}"
+            ),
+            LaunchError::ConfigFile { path, message } => {
+                write!(f, "could not read config file '{path}': {message}")
+            }
+        }
+    }
+}
+
+pub(crate) fn parse_value<T: FromStr>(
+    flag: &str,
+    value: &str,
+    expected: &'static str,
+) -> Result<T, LaunchError> {
+    value.parse().map_err(|_| LaunchError::InvalidValue {
+        flag: flag.to_string(),
+        value: value.to_string(),
+        expected,
+    })
+}
+
+fn parse_switch(flag: &str, value: &str) -> Result<bool, LaunchError> {
+    match value {
+        "on" | "true" => Ok(true),
+        "off" | "false" => Ok(false),
+        _ => Err(LaunchError::InvalidValue {
+            flag: flag.to_string(),
+            value: value.to_string(),
+            expected: "'on' or 'off'",
+        }),
+    }
+}
+
+fn parse_resolution(flag: &str, value: &str) -> Result<(f32, f32), LaunchError> {
+    let invalid = || LaunchError::InvalidValue {
+        flag: flag.to_string(),
+        value: value.to_string(),
+        expected: "a size like 1280x720",
+    };
+    let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+    let width: u32 = width.parse().map_err(|_| invalid())?;
+    let height: u32 = height.parse().map_err(|_| invalid())?;
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+    Ok((width as f32, height as f32))
+}
+
+fn parse_start_level(flag: &str, value: &str) -> Result<u32, LaunchError> {
+    const EXPECTED: &str = "a number from 0 to 999";
+    parse_value(flag, value, EXPECTED)
+        .ok()
+        .filter(|level| *level <= MAX_START_LEVEL)
+        .ok_or_else(|| LaunchError::InvalidValue {
+            flag: flag.to_string(),
+            value: value.to_string(),
+            expected: EXPECTED,
+        })
+}
+
+pub(crate) fn parse_difficulty(flag: &str, value: &str) -> Result<Difficulty, LaunchError> {
+    match value.to_lowercase().as_str() {
+        "story" => Ok(Difficulty::Story),
+        "normal" => Ok(Difficulty::Normal),
+        "hard" => Ok(Difficulty::Hard),
+        "nightmare" => Ok(Difficulty::Nightmare),
+        _ => Err(LaunchError::InvalidValue {
+            flag: flag.to_string(),
+            value: value.to_string(),
+            expected: "story, normal, hard or nightmare",
+        }),
+    }
+}
+
+impl LaunchConfig {
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, LaunchError> {
+        let mut config = Self::default();
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| LaunchError::MissingValue(flag.clone()))
+            };
+            match flag.as_str() {
+                "--help" | "-h" => return Err(LaunchError::HelpRequested),
+                "--config" => {
+                    let path = value()?;
+                    config.merge_file(&path)?;
+                }
+                "--resolution" => {
+                    (config.width, config.height) = parse_resolution(&flag, &value()?)?;
+                }
+                "--windowed" => config.window_mode = LaunchWindowMode::Windowed,
+                "--fullscreen" => config.window_mode = LaunchWindowMode::Fullscreen,
+                "--borderless" => config.window_mode = LaunchWindowMode::Borderless,
+                "--vsync" => config.vsync = parse_switch(&flag, &value()?)?,
+                "--seed" => config.seed = Some(parse_value(&flag, &value()?, "a positive number")?),
+                "--level" => config.start_level = parse_start_level(&flag, &value()?)?,
+                "--difficulty" => config.difficulty = parse_difficulty(&flag, &value()?)?,
+                "--headless" => config.headless = true,
+                "--editor" => config.editor = parse_switch(&flag, &value()?)?,
+                _ => return Err(LaunchError::UnknownFlag(flag)),
+            }
+        }
+        Ok(config)
+    }
+
+    fn merge_file(&mut self, path: &str) -> Result<(), LaunchError> {
+        let config_file_error = |message: String| LaunchError::ConfigFile {
+            path: path.to_string(),
+            message,
+        };
+        let content =
+            fs::read_to_string(path).map_err(|error| config_file_error(error.to_string()))?;
+        // Plain values are accepted for the optional fields, `(seed: 42)` instead of `(seed: Some(42))`
+        let file: LaunchConfigFile = ron::Options::default()
+            .with_default_extension(Extensions::IMPLICIT_SOME)
+            .from_str(&content)
+            .map_err(|error| config_file_error(error.to_string()))?;
+        let invalid_size = |size: f32| !(size.is_finite() && size > 0.0);
+        if file.width.is_some_and(invalid_size) || file.height.is_some_and(invalid_size) {
+            return Err(config_file_error("resolution must be positive".to_string()));
+        }
+        if file
+            .start_level
+            .is_some_and(|level| level > MAX_START_LEVEL)
+        {
+            return Err(config_file_error(format!(
+                "start_level must be at most {MAX_START_LEVEL}"
+            )));
+        }
+        self.width = file.width.unwrap_or(self.width);
+        self.height = file.height.unwrap_or(self.height);
+        self.window_mode = file.window_mode.unwrap_or(self.window_mode);
+        self.vsync = file.vsync.unwrap_or(self.vsync);
+        self.seed = file.seed.or(self.seed);
+        self.start_level = file.start_level.unwrap_or(self.start_level);
+        self.difficulty = file.difficulty.unwrap_or(self.difficulty);
+        self.headless = file.headless.unwrap_or(self.headless);
+        self.editor = file.editor.unwrap_or(self.editor);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<LaunchConfig, LaunchError> {
+        LaunchConfig::from_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn flags_override_defaults() {
+        let config = parse(&[
+            "--resolution",
+            "1920x1080",
+            "--borderless",
+            "--vsync",
+            "off",
+            "--seed",
+            "42",
+            "--level",
+            "3",
+            "--difficulty",
+            "Hard",
+            "--headless",
+            "--editor",
+            "off",
+        ])
+        .unwrap();
+        assert_eq!(
+            config,
+            LaunchConfig {
+                width: 1920.,
+                height: 1080.,
+                window_mode: LaunchWindowMode::Borderless,
+                vsync: false,
+                seed: Some(42),
+                start_level: 3,
+                difficulty: Difficulty::Hard,
+                headless: true,
+                editor: false,
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_values_are_reported() {
+        assert_eq!(
+            parse(&["--resolution", "1920"]),
+            Err(LaunchError::InvalidValue {
+                flag: "--resolution".to_string(),
+                value: "1920".to_string(),
+                expected: "a size like 1280x720",
+            })
+        );
+        assert_eq!(
+            parse(&["--seed"]),
+            Err(LaunchError::MissingValue("--seed".to_string()))
+        );
+        assert_eq!(
+            parse(&["--fast"]),
+            Err(LaunchError::UnknownFlag("--fast".to_string()))
+        );
+        assert!(matches!(
+            parse(&["--level", "-1"]),
+            Err(LaunchError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            parse(&["--level", "4000000000"]),
+            Err(LaunchError::InvalidValue { .. })
+        ));
+        assert_eq!(
+            parse(&["--level", "999"]).unwrap().start_level,
+            MAX_START_LEVEL
+        );
+    }
+
+    #[test]
+    fn config_file_is_overridden_by_later_flags() {
+        let path = std::env::temp_dir().join("ggj_2k33_launch_test.ron");
+        fs::write(
+            &path,
+            "(width: 800, height: 600, window_mode: Fullscreen, seed: 7, difficulty: Nightmare)",
+        )
+        .unwrap();
+        let config = parse(&["--config", path.to_str().unwrap(), "--seed", "8"]).unwrap();
+        assert_eq!((config.width, config.height), (800., 600.));
+        assert_eq!(config.window_mode, LaunchWindowMode::Fullscreen);
+        assert_eq!(config.difficulty, Difficulty::Nightmare);
+        assert_eq!(config.seed, Some(8));
+    }
+
+    #[test]
+    fn invalid_config_file_resolution_is_reported() {
+        let path = std::env::temp_dir().join("ggj_2k33_launch_nan_test.ron");
+        fs::write(&path, "(width: NaN)").unwrap();
+        let result = parse(&["--config", path.to_str().unwrap()]);
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            result,
+            Err(LaunchError::ConfigFile { message, .. }) if message == "resolution must be positive"
+        ));
+    }
+
+    #[test]
+    fn missing_config_file_is_reported() {
+        assert!(matches!(
+            parse(&["--config", "does/not/exist.ron"]),
+            Err(LaunchError::ConfigFile { .. })
+        ));
+    }
+}
@@ -1,43 +1,74 @@
 mod components;
 mod constants;
+pub mod launch;
+mod mods;
 mod plugins;
 mod resource;
+mod save;
+mod scripting;
+pub mod simulation;
 mod systems;
 
 use bevy::{
-    prelude::{default, App, Color, PluginGroup, Resource, States},
-    window::{PresentMode, Window, WindowPlugin, WindowResolution},
+    app::ScheduleRunnerPlugin,
+    prelude::{default, App, Color, NextState, PluginGroup, States},
+    render::{settings::WgpuSettings, RenderPlugin},
+    window::{ExitCondition, PresentMode, Window, WindowMode, WindowPlugin, WindowResolution},
+    winit::WinitPlugin,
     DefaultPlugins,
 };
 use bevy_editor_pls::EditorPlugin;
-use bevy_kira_audio::AudioPlugin;
-use components::{Aim, Alive, Decay, HitCount, Move, Weapon};
+use bevy_kira_audio::{AudioApp, AudioPlugin};
+use components::{Aim, Alive, CameraDirector, Decay, HitCount, MaxHealth, Move, Weapon};
 use constants::{
     BULLETS_DECAYS, PLAYER_BULLETS, PLAYER_BULLETS_SPEED, PLAYER_BULLETS_TTL, PLAYER_COLOR,
     PLAYER_DAMAGE, PLAYER_FIRE_RATE, PLAYER_HEALTH, PLAYER_SPEED,
 };
-use resource::{ChunksMap, LastShot, Score, TotalKilled, TotalSpawned, TotalToSpawn};
+use launch::{LaunchConfig, LaunchWindowMode};
+use mods::Mods;
+use resource::{
+    base_folder, AchievementProgress, Achievements, AnimationLibrary, ChunksMap, DailyScores,
+    EndlessCurve, Eras, GameAssets, GameRng, HighScores, Localization, MusicChannel, MusicDirector,
+    Party, Persistence, Score, Settings, SfxChannel, SfxVoices, Story, StoryProgress, TotalKilled,
+    TotalSpawned, TotalToSpawn, Traits, UiChannel,
+};
+use scripting::{ScriptHooks, Scripts};
 use std::collections::HashMap;
+use systems::achievements::AchievementUnlockedEvent;
+use systems::camera::CameraTraumaEvent;
+use systems::combat_audio::CombatSoundEvent;
+use systems::combat_text::DamageEvent;
 use systems::in_game::{
-    CreateMapEvent, GameOverEvent, MobSpawnEvent, SpawnBulletEvent, WaveDoneEvent,
+    CreateMapEvent, EnemyKilledEvent, GameOverEvent, MobSpawnEvent, SpawnBulletEvent, WaveDoneEvent,
 };
+use systems::particles::ParticleEvent;
+use systems::ui::UiClickEvent;
+
+pub use resource::Difficulty;
 
 use plugins::{
-    game_loop::GameLoopPlugin, game_ui_plugin::GameUIPlugin, launcher_ui_plugin::LauncherUiPlugin,
+    achievements_plugin::AchievementsPlugin, attract_mode_plugin::AttractModePlugin,
+    bot_plugin::BotPlugin, daily_plugin::DailyPlugin, game_loop::GameLoopPlugin,
+    game_ui_plugin::GameUIPlugin, launcher_ui_plugin::LauncherUiPlugin, music_plugin::MusicPlugin,
+    radar_plugin::RadarPlugin, scripting_plugin::ScriptingPlugin, ui_plugin::UiPlugin,
 };
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, States, Default)]
 pub enum AppState {
     #[default]
+    Loading,
     MainMenu,
     InGame,
     Paused,
     RetryMenu,
     LevelMenu,
     PreStartMenu,
+    SettingsMenu,
+    ModsMenu,
+    AchievementsMenu,
 }
 
-#[derive(Resource)]
+// Stats of a player, weakened by the flaws of the chosen parents
 pub struct StatsRes {
     pub player_speed: f32,
     pub player_damage: f32,
@@ -64,49 +95,151 @@ impl Default for StatsRes {
         }
     }
 }
-pub fn run(width: f32, height: f32) {
-    let mut app = App::new();
-    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+fn window_plugin(config: &LaunchConfig) -> WindowPlugin {
+    if config.headless {
+        return WindowPlugin {
+            primary_window: None,
+            exit_condition: ExitCondition::DontExit,
+            close_when_requested: false,
+        };
+    }
+    WindowPlugin {
         primary_window: Some(Window {
             title: "BACK TO THE ROOTS".to_string(),
-            resolution: WindowResolution::new(width, height),
-            present_mode: PresentMode::AutoVsync,
+            resolution: WindowResolution::new(config.width, config.height),
+            mode: match config.window_mode {
+                LaunchWindowMode::Windowed => WindowMode::Windowed,
+                LaunchWindowMode::Fullscreen => WindowMode::Fullscreen,
+                LaunchWindowMode::Borderless => WindowMode::BorderlessFullscreen,
+            },
+            present_mode: if config.vsync {
+                PresentMode::AutoVsync
+            } else {
+                PresentMode::AutoNoVsync
+            },
             ..default()
         }),
         ..default()
-    }))
-    .add_plugin(AudioPlugin)
-    .insert_resource(TotalToSpawn::default())
-    .insert_resource(TotalSpawned::default())
-    .insert_resource(TotalKilled::default())
-    .insert_resource(LastShot::default())
-    .insert_resource(Score::default())
-    .insert_resource(ChunksMap {
-        chunks: HashMap::new(),
-    })
-    .add_event::<SpawnBulletEvent>()
-    .add_event::<MobSpawnEvent>()
-    .add_event::<GameOverEvent>()
-    .add_event::<WaveDoneEvent>()
-    .add_event::<CreateMapEvent>()
-    // To change to AppState::MainMenu when loop is finished
-    .add_state::<AppState>()
-    .init_resource::<StatsRes>()
-    //
-    .add_plugin(LauncherUiPlugin)
-    .add_plugin(GameUIPlugin)
-    .add_plugin(GameLoopPlugin);
+    }
+}
+
+pub fn build_app(config: &LaunchConfig) -> App {
+    let mut app = App::new();
+    if config.headless {
+        app.add_plugins(
+            DefaultPlugins
+                .set(window_plugin(config))
+                // No GPU needed when nothing is drawn
+                .set(RenderPlugin {
+                    wgpu_settings: WgpuSettings {
+                        backends: None,
+                        ..default()
+                    },
+                })
+                .disable::<WinitPlugin>(),
+        )
+        .add_plugin(ScheduleRunnerPlugin);
+    } else {
+        app.add_plugins(DefaultPlugins.set(window_plugin(config)));
+    }
+    let settings = Settings::load();
+    // Mod packs extend the shipped content before anything reads it
+    let (mut eras, mut story) = (Eras::default(), Story::default());
+    let (mut traits, mut endless_curve) = (Traits::default(), EndlessCurve::default());
+    let mut hooks = ScriptHooks::load();
+    let mut mods = Mods::discover(&base_folder(), &settings, &eras, &story);
+    mods.merge(
+        &mut eras,
+        &mut story,
+        &mut traits,
+        &mut endless_curve,
+        &mut hooks,
+    );
+    let mut total_to_spawn = TotalToSpawn::default();
+    total_to_spawn.update_paramter_for_level_id(
+        config.start_level,
+        config.difficulty,
+        &endless_curve,
+    );
+    app.add_plugin(AudioPlugin)
+        .add_audio_channel::<MusicChannel>()
+        .add_audio_channel::<SfxChannel>()
+        .add_audio_channel::<UiChannel>()
+        .insert_resource(Localization::new(settings.language))
+        .insert_resource(settings)
+        .init_resource::<Persistence>()
+        .init_resource::<SfxVoices>()
+        .init_resource::<MusicDirector>()
+        .insert_resource(total_to_spawn)
+        .insert_resource(endless_curve)
+        .insert_resource(TotalSpawned::default())
+        .insert_resource(TotalKilled::default())
+        .insert_resource(Score::starting_at(config.start_level, config.difficulty))
+        .insert_resource(GameRng::new(config.seed))
+        .insert_resource(HighScores::load())
+        .insert_resource(DailyScores::load())
+        .insert_resource(StoryProgress::load())
+        .insert_resource(AchievementProgress::load())
+        .insert_resource(ChunksMap {
+            chunks: HashMap::new(),
+        })
+        .add_event::<SpawnBulletEvent>()
+        .add_event::<MobSpawnEvent>()
+        .add_event::<GameOverEvent>()
+        .add_event::<WaveDoneEvent>()
+        .add_event::<EnemyKilledEvent>()
+        .add_event::<CreateMapEvent>()
+        .add_event::<CombatSoundEvent>()
+        .add_event::<CameraTraumaEvent>()
+        .add_event::<ParticleEvent>()
+        .add_event::<DamageEvent>()
+        .add_event::<UiClickEvent>()
+        .add_event::<AchievementUnlockedEvent>()
+        // To change to AppState::MainMenu when loop is finished
+        .add_state::<AppState>()
+        .init_resource::<Party>()
+        .init_resource::<AnimationLibrary>()
+        .insert_resource(eras)
+        .insert_resource(story)
+        .init_resource::<Achievements>()
+        .insert_resource(traits)
+        .insert_resource(mods)
+        .insert_resource(Scripts::new(&hooks))
+        .init_resource::<GameAssets>()
+        //
+        .add_plugin(UiPlugin)
+        .add_plugin(LauncherUiPlugin)
+        .add_plugin(GameUIPlugin)
+        .add_plugin(GameLoopPlugin)
+        .add_plugin(MusicPlugin)
+        .add_plugin(RadarPlugin)
+        .add_plugin(BotPlugin)
+        .add_plugin(ScriptingPlugin)
+        .add_plugin(AchievementsPlugin)
+        .add_plugin(DailyPlugin)
+        .add_plugin(AttractModePlugin);
 
     //
 
     app.register_type::<Alive>();
+    app.register_type::<MaxHealth>();
     app.register_type::<Move>();
     app.register_type::<Decay>();
     app.register_type::<HitCount>();
     app.register_type::<Aim>();
     app.register_type::<Weapon>();
-    if cfg!(debug_assertions) {
+    // Tune the camera live from the editor
+    app.register_type::<CameraDirector>();
+    if config.editor && !config.headless {
         app.add_plugin(EditorPlugin);
     }
-    app.run();
+    // Nobody can click through the menus without a window
+    if config.headless {
+        app.insert_resource(NextState(Some(AppState::InGame)));
+    }
+    app
+}
+
+pub fn run(config: LaunchConfig) {
+    build_app(&config).run();
 }
@@ -1,6 +1,15 @@
-use ggj_2k33::run;
+use ggj_2k33::{
+    launch::{LaunchConfig, LaunchError},
+    run,
+};
 
 fn main() {
-    // X, Y resolution
-    run(1280., 720.);
+    match LaunchConfig::from_args(std::env::args().skip(1)) {
+        Ok(config) => run(config),
+        Err(LaunchError::HelpRequested) => println!("{}", LaunchError::HelpRequested),
+        Err(error) => {
+            eprintln!("error: {error}\n\nRun with --help to list the options.");
+            std::process::exit(2);
+        }
+    }
 }
@@ -0,0 +1,436 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    log::{info, warn},
+    prelude::Resource,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{
+    components::DebufChoices,
+    constants::{ASSETS_FOLDER, MODS_FOLDER, MOD_MANIFEST},
+    resource::{EndlessCurve, EnemyKind, Era, Eras, Settings, Story, StoryBeat, Traits},
+    scripting::ScriptHooks,
+};
+
+// What a pack says about itself, in the manifest at its root
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    name: String,
+    version: String,
+    #[serde(default)]
+    description: String,
+}
+
+// An extra kind of enemy for an era, shipped or added by the same pack
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EnemyArchetype {
+    // Name key of the era
+    era: String,
+    #[serde(default)]
+    robot: bool,
+    kind: EnemyKind,
+}
+
+// Everything a pack adds, with its file paths made reachable from the assets folder
+#[derive(Default)]
+struct PackContent {
+    eras: Vec<Era>,
+    enemies: Vec<EnemyArchetype>,
+    traits: BTreeMap<DebufChoices, f32>,
+    waves: Option<EndlessCurve>,
+    beats: Vec<StoryBeat>,
+    scripts: ScriptHooks,
+}
+
+pub struct ModPack {
+    // Folder name, the settings refer to the pack by it
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    // Merged into the game on this launch
+    pub enabled: bool,
+    // A pack with any error is left out as a whole
+    pub errors: Vec<String>,
+    content: PackContent,
+}
+
+impl ModPack {
+    pub fn loaded(&self) -> bool {
+        self.enabled && self.errors.is_empty()
+    }
+}
+
+// Content packs found next to the game, in folder name order
+#[derive(Resource, Default)]
+pub struct Mods {
+    pub packs: Vec<ModPack>,
+}
+
+// Reads the files of one pack and keeps track of what is wrong with them
+struct PackReader<'a> {
+    // Folder holding both the shipped assets and the mods
+    base: &'a Path,
+    dir: &'a Path,
+    errors: Vec<String>,
+}
+
+impl PackReader<'_> {
+    fn parse<T: DeserializeOwned>(&mut self, path: &Path) -> Option<T> {
+        let name = path.strip_prefix(self.dir).unwrap_or(path).display();
+        let parsed = fs::read_to_string(path)
+            .map_err(|error| error.to_string())
+            .and_then(|content| ron::from_str(&content).map_err(|error| error.to_string()));
+        parsed
+            .map_err(|error| self.errors.push(format!("{name}: {error}")))
+            .ok()
+    }
+
+    fn file<T: DeserializeOwned>(&mut self, name: &str) -> Option<T> {
+        let path = self.dir.join(name);
+        path.is_file().then(|| self.parse(&path)).flatten()
+    }
+
+    fn folder<T: DeserializeOwned>(&mut self, name: &str) -> Vec<(String, T)> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(self.dir.join(name))
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+                    .collect()
+            })
+            .unwrap_or_default();
+        paths.sort();
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                let value = self.parse(&path)?;
+                let name = path.strip_prefix(self.dir).unwrap_or(&path);
+                Some((name.display().to_string(), value))
+            })
+            .collect()
+    }
+
+    // Pack files win over the shipped assets of the same name
+    fn asset(&mut self, path: &mut String) {
+        let in_pack = self.dir.join(&*path);
+        if in_pack.is_file() {
+            // The asset server reads from the assets folder, packs sit next to it
+            let from_base = in_pack.strip_prefix(self.base).unwrap_or(&in_pack);
+            *path = Path::new("..").join(from_base).display().to_string();
+        } else if !self.base.join(ASSETS_FOLDER).join(&*path).is_file() {
+            self.errors.push(format!("missing file {path}"));
+        }
+    }
+
+    // Scripts are read by the game itself rather than the asset server
+    fn script(&mut self, path: &mut String) {
+        let in_pack = self.dir.join(&*path);
+        let shipped = self.base.join(ASSETS_FOLDER).join(&*path);
+        if in_pack.is_file() {
+            *path = in_pack.display().to_string();
+        } else if shipped.is_file() {
+            *path = shipped.display().to_string();
+        } else {
+            self.errors.push(format!("missing script {path}"));
+        }
+    }
+
+    fn kind(&mut self, kind: &mut EnemyKind) {
+        let invalid = |value: f32| !(value.is_finite() && value > 0.0);
+        if invalid(kind.health) || invalid(kind.speed) {
+            self.errors.push(format!(
+                "enemy {}: health and speed must be above 0",
+                kind.atlas
+            ));
+        }
+        self.asset(&mut kind.atlas);
+    }
+
+    fn era(&mut self, file: &str, era: &mut Era) {
+        if let Some(problem) = era.problem() {
+            self.errors.push(format!("{file}: era {problem}"));
+        }
+        era.chunk_tiles.iter_mut().for_each(|tile| self.asset(tile));
+        self.asset(&mut era.music);
+        era.natives
+            .iter_mut()
+            .chain(era.robots.iter_mut())
+            .for_each(|kind| self.kind(kind));
+    }
+
+    fn content(&mut self, eras: &Eras, story: &Story) -> PackContent {
+        let mut content = PackContent::default();
+        for (file, mut era) in self.folder::<Era>("eras") {
+            self.era(&file, &mut era);
+            content.eras.push(era);
+        }
+        for (file, mut enemy) in self.folder::<EnemyArchetype>("enemies") {
+            let known =
+                eras.has_era(&enemy.era) || content.eras.iter().any(|era| era.name == enemy.era);
+            if !known {
+                self.errors
+                    .push(format!("{file}: no era is named {}", enemy.era));
+            }
+            self.kind(&mut enemy.kind);
+            content.enemies.push(enemy);
+        }
+        if let Some(traits) = self.file::<BTreeMap<DebufChoices, f32>>("traits.ron") {
+            for (flaw, factor) in &traits {
+                if let Some(problem) = Traits::problem(*flaw, *factor) {
+                    self.errors
+                        .push(format!("traits.ron: {} factor {problem}", flaw.key()));
+                }
+            }
+            content.traits = traits;
+        }
+        content.waves = self.file::<EndlessCurve>("waves.ron");
+        if let Some(problem) = content.waves.as_ref().and_then(EndlessCurve::problem) {
+            self.errors.push(format!("waves.ron: {problem}"));
+        }
+        let mut ids = BTreeSet::new();
+        for mut beat in self.file::<Vec<StoryBeat>>("story.ron").unwrap_or_default() {
+            if story.has_beat(&beat.id) || !ids.insert(beat.id.clone()) {
+                self.errors
+                    .push(format!("story.ron: beat {} already exists", beat.id));
+            }
+            for line in &mut beat.lines {
+                if let Some(portrait) = &mut line.portrait {
+                    self.asset(portrait);
+                }
+            }
+            content.beats.push(beat);
+        }
+        if let Some(mut hooks) = self.file::<ScriptHooks>("scripts.ron") {
+            hooks.scripts_mut().for_each(|script| self.script(script));
+            content.scripts = hooks;
+        }
+        content
+    }
+}
+
+impl Mods {
+    // Every folder with a manifest in the mods folder of `base` is a pack, the shipped eras and
+    // story tell which names are taken
+    pub fn discover(base: &Path, settings: &Settings, eras: &Eras, story: &Story) -> Self {
+        let mut dirs: Vec<PathBuf> = fs::read_dir(base.join(MODS_FOLDER))
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.path())
+                    .filter(|path| path.join(MOD_MANIFEST).is_file())
+                    .collect()
+            })
+            .unwrap_or_default();
+        dirs.sort();
+        let packs = dirs
+            .iter()
+            .map(|dir| {
+                let id = dir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let mut reader = PackReader {
+                    base,
+                    dir,
+                    errors: vec![],
+                };
+                let manifest = reader.file::<Manifest>(MOD_MANIFEST);
+                if manifest
+                    .as_ref()
+                    .is_some_and(|manifest| manifest.name.is_empty())
+                {
+                    reader
+                        .errors
+                        .push(format!("{MOD_MANIFEST}: the pack needs a name"));
+                }
+                let content = reader.content(eras, story);
+                // Unreadable packs are still listed under their folder name
+                let (name, version, description) = match manifest {
+                    Some(manifest) if !manifest.name.is_empty() => {
+                        (manifest.name, manifest.version, manifest.description)
+                    }
+                    _ => (id.clone(), String::new(), String::new()),
+                };
+                ModPack {
+                    enabled: settings.mod_enabled(&id),
+                    id,
+                    name,
+                    version,
+                    description,
+                    errors: reader.errors,
+                    content,
+                }
+            })
+            .collect();
+        Self { packs }
+    }
+
+    // Enabled packs without errors go into the registries the game reads, later packs win
+    pub fn merge(
+        &mut self,
+        eras: &mut Eras,
+        story: &mut Story,
+        traits: &mut Traits,
+        endless_curve: &mut EndlessCurve,
+        hooks: &mut ScriptHooks,
+    ) {
+        for pack in &mut self.packs {
+            if !pack.errors.is_empty() {
+                warn!("Mod {} not loaded: {}", pack.id, pack.errors.join(", "));
+            }
+            if !pack.loaded() {
+                continue;
+            }
+            let content = std::mem::take(&mut pack.content);
+            eras.extend(content.eras);
+            for enemy in content.enemies {
+                eras.add_enemy(&enemy.era, enemy.robot, enemy.kind);
+            }
+            for (flaw, factor) in content.traits {
+                traits.set_factor(flaw, factor);
+            }
+            if let Some(waves) = content.waves {
+                *endless_curve = waves;
+            }
+            story.extend(content.beats);
+            hooks.extend(content.scripts);
+            info!("Loaded mod {} {}", pack.name, pack.version);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_pack(root: &Path, id: &str, files: &[(&str, &str)]) {
+        for (name, content) in files {
+            let path = root.join(id).join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+    }
+
+    #[test]
+    fn valid_packs_are_merged_and_broken_ones_reported() {
+        let base = std::env::temp_dir().join(format!("mods_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join(MODS_FOLDER);
+        // Shipped assets the packs refer to
+        write_pack(
+            &base,
+            ASSETS_FOLDER,
+            &[
+                ("sounds/in_game_0.ogg", ""),
+                ("images/mob_atlas_0.png", ""),
+                ("images/mob_atlas_1.png", ""),
+            ],
+        );
+        write_pack(
+            &root,
+            "ice_age",
+            &[
+                ("pack.ron", r#"(name: "Ice age", version: "1.0")"#),
+                ("tiles/ice.png", ""),
+                (
+                    "eras/ice_age.ron",
+                    r#"(
+                        name: "Ice age",
+                        lore: "Mammoths everywhere",
+                        start: Level(8),
+                        chunk_tiles: ["tiles/ice.png"],
+                        music: "sounds/in_game_0.ogg",
+                        natives: [(atlas: "images/mob_atlas_0.png", health: 3.0)],
+                        robots: [],
+                        tint: (0.8, 0.9, 1.0),
+                    )"#,
+                ),
+                (
+                    "enemies/cyborg.ron",
+                    r#"(era: "era.future.name", robot: true, kind: (atlas: "images/mob_atlas_1.png", speed: 1.5))"#,
+                ),
+                ("traits.ron", "{ speed: 0.5 }"),
+                ("waves.ron", "(enemy_growth: 0.3)"),
+                ("scripts/thaw.rhai", ""),
+                (
+                    "scripts.ron",
+                    r#"(traits: [(flaw: speed, script: "scripts/thaw.rhai")])"#,
+                ),
+            ],
+        );
+        write_pack(
+            &root,
+            "broken",
+            &[
+                (
+                    "pack.ron",
+                    r#"(name: "Broken", version: "0.1", colour: "red")"#,
+                ),
+                ("traits.ron", "{ fire_rate: 0.5, damage: NaN }"),
+                (
+                    "enemies/lost.ron",
+                    r#"(era: "Atlantis", kind: (atlas: "images/nowhere.png"))"#,
+                ),
+                (
+                    "enemies/undying.ron",
+                    r#"(era: "era.future.name", kind: (atlas: "images/mob_atlas_0.png", health: NaN))"#,
+                ),
+            ],
+        );
+        write_pack(
+            &root,
+            "disabled",
+            &[
+                ("pack.ron", r#"(name: "Disabled", version: "1.0")"#),
+                ("traits.ron", "{ damage: 0.1 }"),
+            ],
+        );
+
+        let mut settings = Settings::default();
+        settings.disabled_mods.insert("disabled".to_string());
+        let (mut eras, mut story) = (Eras::builtin(), Story::default());
+        let (mut traits, mut endless_curve) = (Traits::default(), EndlessCurve::default());
+        let mut hooks = ScriptHooks::default();
+        let mut mods = Mods::discover(&base, &settings, &eras, &story);
+        mods.merge(
+            &mut eras,
+            &mut story,
+            &mut traits,
+            &mut endless_curve,
+            &mut hooks,
+        );
+        fs::remove_dir_all(&base).unwrap();
+
+        let ids: Vec<&str> = mods.packs.iter().map(|pack| pack.id.as_str()).collect();
+        assert_eq!(ids, ["broken", "disabled", "ice_age"]);
+        let loaded: Vec<bool> = mods.packs.iter().map(ModPack::loaded).collect();
+        assert_eq!(loaded, [false, false, true]);
+        // Unknown manifest field, fire rate flaw making the player stronger, damage factor not a
+        // number, unknown era, missing atlas, enemy health not a number
+        assert_eq!(mods.packs[0].errors.len(), 6, "{:?}", mods.packs[0].errors);
+
+        let ice_age = eras.at(8);
+        assert_eq!(ice_age.name, "Ice age");
+        // Pack files point into the pack, shipped ones stay as they are
+        assert_eq!(ice_age.chunk_tiles[0], "../mods/ice_age/tiles/ice.png");
+        assert_eq!(ice_age.music, "sounds/in_game_0.ogg");
+        assert!(eras.at(0).robots.iter().any(|kind| kind.speed == 1.5));
+        assert_eq!(traits.factor(DebufChoices::Speed), 0.5);
+        assert_eq!(
+            traits.factor(DebufChoices::Damage),
+            Traits::default().factor(DebufChoices::Damage)
+        );
+        assert_eq!(endless_curve.enemy_growth, 0.3);
+        assert_eq!(hooks.traits[0].flaw, DebufChoices::Speed);
+        assert!(Path::new(&hooks.traits[0].script).ends_with("ice_age/scripts/thaw.rhai"));
+    }
+}
@@ -0,0 +1,21 @@
+use bevy::prelude::{App, IntoSystemAppConfig, OnEnter, Plugin};
+
+use crate::{
+    systems::achievements::{
+        achievement_toast_update, achievements_wave_cleared, achievements_wave_start,
+        spawn_achievement_toasts,
+    },
+    AppState,
+};
+
+// Achievements checked between waves, and their unlock toasts over every screen
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(achievements_wave_cleared.in_schedule(OnEnter(AppState::LevelMenu)))
+            .add_system(achievements_wave_start.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(spawn_achievement_toasts)
+            .add_system(achievement_toast_update);
+    }
+}
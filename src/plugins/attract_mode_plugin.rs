@@ -0,0 +1,35 @@
+use bevy::prelude::{App, IntoSystemAppConfig, IntoSystemConfig, OnEnter, OnUpdate, Plugin};
+
+use crate::{
+    resource::AttractMode,
+    systems::{
+        attract_mode::{
+            attract_demo_running, attract_demo_update, attract_idle_update, end_attract_demo,
+            setup_attract_overlay,
+        },
+        in_game::{change_level, game_over},
+    },
+    AppState,
+};
+
+pub struct AttractModePlugin;
+
+impl Plugin for AttractModePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AttractMode>()
+            .add_system(attract_idle_update.in_set(OnUpdate(AppState::MainMenu)))
+            .add_system(end_attract_demo.in_schedule(OnEnter(AppState::MainMenu)))
+            .add_system(
+                setup_attract_overlay
+                    .run_if(attract_demo_running)
+                    .in_schedule(OnEnter(AppState::InGame)),
+            )
+            .add_system(
+                attract_demo_update
+                    .after(change_level)
+                    .after(game_over)
+                    .run_if(attract_demo_running)
+                    .in_set(OnUpdate(AppState::InGame)),
+            );
+    }
+}
@@ -0,0 +1,61 @@
+use bevy::prelude::{
+    resource_exists, App, IntoSystemAppConfig, IntoSystemConfig, OnEnter, OnUpdate, Plugin,
+};
+
+use crate::{
+    resource::{Bot, BotLog},
+    systems::{
+        bot::{
+            bot_control, bot_end_wave, bot_pick_parent, bot_record_run, bot_retry, bot_start_wave,
+        },
+        in_game::{
+            firing_bullet_emit, key_input_update, mouse_button_input_update, player_aim_update,
+            transform_update,
+        },
+    },
+    AppState,
+};
+
+// Plays the game on its own while a Bot resource exists
+pub struct BotPlugin;
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BotLog>()
+            .add_system(
+                bot_control
+                    .after(player_aim_update)
+                    .after(key_input_update)
+                    .after(mouse_button_input_update)
+                    .before(transform_update)
+                    .before(firing_bullet_emit)
+                    .run_if(resource_exists::<Bot>())
+                    .in_set(OnUpdate(AppState::InGame)),
+            )
+            .add_system(
+                bot_start_wave
+                    .run_if(resource_exists::<Bot>())
+                    .in_schedule(OnEnter(AppState::InGame)),
+            )
+            .add_system(
+                bot_end_wave
+                    .run_if(resource_exists::<Bot>())
+                    .in_schedule(OnEnter(AppState::LevelMenu)),
+            )
+            .add_system(
+                bot_pick_parent
+                    .run_if(resource_exists::<Bot>())
+                    .in_set(OnUpdate(AppState::LevelMenu)),
+            )
+            .add_system(
+                bot_record_run
+                    .run_if(resource_exists::<Bot>())
+                    .in_schedule(OnEnter(AppState::RetryMenu)),
+            )
+            .add_system(
+                bot_retry
+                    .run_if(resource_exists::<Bot>())
+                    .in_set(OnUpdate(AppState::RetryMenu)),
+            );
+    }
+}
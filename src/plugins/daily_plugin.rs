@@ -0,0 +1,40 @@
+use bevy::prelude::{
+    resource_exists, App, IntoSystemAppConfig, IntoSystemConfig, OnEnter, OnUpdate, Plugin,
+};
+
+use crate::{
+    resource::DailyRun,
+    systems::{
+        daily::{daily_button, daily_parents_seed, daily_wave_seed, end_daily_run},
+        level_menu::setup_level_menu,
+        main_menu::setup_main_menu,
+        scripting::script_wave_start,
+    },
+    AppState,
+};
+
+// Daily Ancestry runs, reseeded on every wave and parent choice from the day
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(daily_button.in_set(OnUpdate(AppState::MainMenu)))
+            .add_system(
+                end_daily_run
+                    .before(setup_main_menu)
+                    .in_schedule(OnEnter(AppState::MainMenu)),
+            )
+            .add_system(
+                daily_wave_seed
+                    .before(script_wave_start)
+                    .run_if(resource_exists::<DailyRun>())
+                    .in_schedule(OnEnter(AppState::InGame)),
+            )
+            .add_system(
+                daily_parents_seed
+                    .before(setup_level_menu)
+                    .run_if(resource_exists::<DailyRun>())
+                    .in_schedule(OnEnter(AppState::LevelMenu)),
+            );
+    }
+}
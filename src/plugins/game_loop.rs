@@ -1,6 +1,16 @@
-use crate::{systems::in_game::*, AppState};
+use crate::{
+    systems::{
+        camera::{camera_director_update, camera_trauma_update},
+        combat_audio::play_combat_sounds,
+        combat_text::{floating_text_update, spawn_damage_numbers},
+        in_game::*,
+        particles::{particle_update, spawn_particles},
+    },
+    AppState,
+};
 use bevy::prelude::{
-    App, IntoSystemAppConfig, IntoSystemConfigs, OnEnter, OnExit, OnUpdate, Plugin,
+    App, IntoSystemAppConfig, IntoSystemConfig, IntoSystemConfigs, OnEnter, OnExit, OnUpdate,
+    Plugin,
 };
 pub struct GameLoopPlugin;
 
@@ -10,7 +20,6 @@ impl Plugin for GameLoopPlugin {
             .add_systems(
                 (
                     player_aim_update,
-                    camera_position_update,
                     make_map,
                     load_chunks,
                     mouse_button_input_update,
@@ -35,6 +44,13 @@ impl Plugin for GameLoopPlugin {
                     change_level,
                     animate_sprite,
                     game_over,
+                    play_combat_sounds,
+                    camera_trauma_update,
+                    spawn_particles,
+                    particle_update,
+                    spawn_damage_numbers,
+                    floating_text_update,
+                    camera_director_update.after(camera_trauma_update),
                 )
                     .in_set(OnUpdate(AppState::InGame)),
             )
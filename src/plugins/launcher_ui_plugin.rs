@@ -1,11 +1,19 @@
 use bevy::prelude::{
-    App, IntoSystemAppConfig, IntoSystemConfig, OnEnter, OnExit, OnUpdate, Plugin,
+    App, IntoSystemAppConfig, IntoSystemConfig, IntoSystemConfigs, OnEnter, OnExit, OnUpdate,
+    Plugin,
 };
 
 use crate::{
     systems::{
-        main_menu::{clean_main_menu, setup_main_menu, start_button},
+        achievements_menu::{clean_achievements_menu, setup_achievements_menu},
+        loading::{clean_loading, loading_update, setup_loading},
+        main_menu::{clean_main_menu, main_menu_buttons, players_button, setup_main_menu},
+        mods_menu::{clean_mods_menu, mods_menu_button, setup_mods_menu},
         prestart_menu::{clean_pre_start_menu, ingame_button, setup_pre_start_menu},
+        settings_menu::{
+            apply_audio_settings, clean_settings_menu, settings_button, setup_settings_menu,
+            update_localized_texts,
+        },
     },
     AppState,
 };
@@ -14,11 +22,24 @@ pub struct LauncherUiPlugin;
 
 impl Plugin for LauncherUiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(setup_main_menu.in_schedule(OnEnter(AppState::MainMenu)))
-            .add_system(start_button.in_set(OnUpdate(AppState::MainMenu)))
+        app.add_system(setup_loading.in_schedule(OnEnter(AppState::Loading)))
+            .add_system(loading_update.in_set(OnUpdate(AppState::Loading)))
+            .add_system(clean_loading.in_schedule(OnExit(AppState::Loading)))
+            .add_system(setup_main_menu.in_schedule(OnEnter(AppState::MainMenu)))
+            .add_systems((main_menu_buttons, players_button).in_set(OnUpdate(AppState::MainMenu)))
             .add_system(clean_main_menu.in_schedule(OnExit(AppState::MainMenu)))
             .add_system(setup_pre_start_menu.in_schedule(OnEnter(AppState::PreStartMenu)))
             .add_system(ingame_button.in_set(OnUpdate(AppState::PreStartMenu)))
-            .add_system(clean_pre_start_menu.in_schedule(OnExit(AppState::PreStartMenu)));
+            .add_system(clean_pre_start_menu.in_schedule(OnExit(AppState::PreStartMenu)))
+            .add_system(setup_settings_menu.in_schedule(OnEnter(AppState::SettingsMenu)))
+            .add_system(settings_button.in_set(OnUpdate(AppState::SettingsMenu)))
+            .add_system(clean_settings_menu.in_schedule(OnExit(AppState::SettingsMenu)))
+            .add_system(setup_mods_menu.in_schedule(OnEnter(AppState::ModsMenu)))
+            .add_system(mods_menu_button.in_set(OnUpdate(AppState::ModsMenu)))
+            .add_system(clean_mods_menu.in_schedule(OnExit(AppState::ModsMenu)))
+            .add_system(setup_achievements_menu.in_schedule(OnEnter(AppState::AchievementsMenu)))
+            .add_system(clean_achievements_menu.in_schedule(OnExit(AppState::AchievementsMenu)))
+            .add_system(apply_audio_settings)
+            .add_system(update_localized_texts);
     }
 }
@@ -1,3 +1,11 @@
+pub mod achievements_plugin;
+pub mod attract_mode_plugin;
+pub mod bot_plugin;
+pub mod daily_plugin;
 pub mod game_loop;
 pub mod game_ui_plugin;
 pub mod launcher_ui_plugin;
+pub mod music_plugin;
+pub mod radar_plugin;
+pub mod scripting_plugin;
+pub mod ui_plugin;
@@ -0,0 +1,11 @@
+use bevy::prelude::{App, Plugin};
+
+use crate::systems::music::music_director_update;
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(music_director_update);
+    }
+}
@@ -0,0 +1,25 @@
+use bevy::prelude::{
+    App, IntoSystemAppConfig, IntoSystemConfig, IntoSystemConfigs, OnEnter, OnUpdate, Plugin,
+};
+
+use crate::{
+    resource::RadarRefresh,
+    systems::radar::{enemy_indicator_update, minimap_update, radar_refresh_tick, setup_radar},
+    AppState,
+};
+
+// Off-screen enemy indicators and the minimap
+pub struct RadarPlugin;
+
+impl Plugin for RadarPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RadarRefresh>()
+            .add_system(setup_radar.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(radar_refresh_tick.in_set(OnUpdate(AppState::InGame)))
+            .add_systems(
+                (enemy_indicator_update, minimap_update)
+                    .after(radar_refresh_tick)
+                    .in_set(OnUpdate(AppState::InGame)),
+            );
+    }
+}
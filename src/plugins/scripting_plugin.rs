@@ -0,0 +1,28 @@
+use bevy::prelude::{
+    App, IntoSystemAppConfig, IntoSystemConfig, IntoSystemConfigs, OnEnter, OnUpdate, Plugin,
+};
+
+use crate::{
+    systems::{
+        in_game::{change_level, despawn_health},
+        scripting::{reload_scripts, script_tick, script_wave_done, script_wave_start},
+    },
+    AppState,
+};
+
+// Wave and flaw scripts, reloaded whenever their file changes
+pub struct ScriptingPlugin;
+
+impl Plugin for ScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(script_wave_start.in_schedule(OnEnter(AppState::InGame)))
+            .add_systems(
+                (
+                    script_tick.after(despawn_health),
+                    script_wave_done.before(change_level),
+                )
+                    .in_set(OnUpdate(AppState::InGame)),
+            )
+            .add_system(reload_scripts);
+    }
+}
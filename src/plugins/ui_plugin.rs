@@ -0,0 +1,21 @@
+use bevy::prelude::{App, Plugin};
+
+use crate::systems::ui::{
+    apply_text_scale, back_to_main_menu, button_clicks, button_states, menu_navigation,
+    slide_in_update,
+};
+
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems((
+            button_states,
+            button_clicks,
+            menu_navigation,
+            back_to_main_menu,
+            slide_in_update,
+            apply_text_scale,
+        ));
+    }
+}
@@ -1,43 +1,242 @@
 use core::f32;
 
-use bevy::prelude::Resource;
-use std::collections::HashMap;
+use bevy::{
+    asset::{AssetServer, Assets, FileAssetIo, HandleUntyped},
+    prelude::{
+        default, warn, Color, Deref, DerefMut, FromWorld, Handle, Image, Resource, Vec2, World,
+    },
+    sprite::TextureAtlas,
+    text::Font,
+    time::{Timer, TimerMode},
+};
+use bevy_kira_audio::{AudioInstance, AudioSource};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Display,
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use crate::constants::{
-    BEGIN_DATE, DECREMENT_DATE_PER_LEVEL, ENEMY_BY_LVL, MAX_WAVE_CALIBRATION, SPAWN_TICK_BY_LVL,
-    SPEED_SPAWN_BY_LVL,
+    ACHIEVEMENTS_FILE, ATTRACT_IDLE_SECONDS, BEGIN_DATE, DAILY_SCORES_FILE,
+    DECREMENT_DATE_PER_LEVEL, DEFAULT_VOLUME, ENDLESS_BURST_GROWTH, ENDLESS_DENSITY_GROWTH,
+    ENDLESS_ELITE_RAMP, ENDLESS_ENEMY_GROWTH, ENDLESS_MAX_ELITE_RATIO, ENDLESS_MAX_ROBOT_RATIO,
+    ENDLESS_MIN_SPAWN_DELAY, ENDLESS_ROBOT_RAMP, ENEMY_BY_LVL, ERAS_FOLDER, FLAW_BULLETS,
+    FLAW_BULLETS_SPEED, FLAW_DAMAGE, FLAW_FIRE_RATE, FLAW_PIERCING, FLAW_SPEED, HIGH_SCORES_FILE,
+    MAX_DAILY_SCORES, MAX_HIGH_SCORES, MAX_WAVE_CALIBRATION, MOB_COLOR_HURT,
+    MOB_COLOR_HURT_BLUE_YELLOW, MOB_COLOR_HURT_RED_GREEN, MOB_ROBOT_RATIO,
+    PARENT_COLORS_BLUE_YELLOW, PARENT_COLORS_RED_GREEN, RADAR_REFRESH_SECONDS, SECONDS_PER_DAY,
+    SETTINGS_FILE, SPAWN_TICK_BY_LVL, SPEED_SPAWN_BY_LVL, STORY_FILE, TEXT_SCALES, UI_FONT,
 };
+use crate::{
+    components::{DebufChoices, Debuff},
+    save, StatsRes,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Story,
+    #[default]
+    Normal,
+    Hard,
+    Nightmare,
+}
+
+// Multipliers applied on top of the wave calibration tables and the mob starting stats
+pub struct DifficultyModifiers {
+    pub enemy_amount: f32,
+    pub spawn_rate: f32,
+    pub burst_size: f32,
+    pub enemy_health: f32,
+    pub enemy_speed: f32,
+    pub enemy_damage: f32,
+}
+
+impl Difficulty {
+    pub fn modifiers(&self) -> DifficultyModifiers {
+        match self {
+            Difficulty::Story => DifficultyModifiers {
+                enemy_amount: 0.6,
+                spawn_rate: 0.7,
+                burst_size: 0.5,
+                enemy_health: 0.5,
+                enemy_speed: 0.8,
+                enemy_damage: 0.5,
+            },
+            Difficulty::Normal => DifficultyModifiers {
+                enemy_amount: 1.0,
+                spawn_rate: 1.0,
+                burst_size: 1.0,
+                enemy_health: 1.0,
+                enemy_speed: 1.0,
+                enemy_damage: 1.0,
+            },
+            Difficulty::Hard => DifficultyModifiers {
+                enemy_amount: 1.3,
+                spawn_rate: 1.25,
+                burst_size: 1.5,
+                enemy_health: 1.5,
+                enemy_speed: 1.1,
+                enemy_damage: 1.0,
+            },
+            Difficulty::Nightmare => DifficultyModifiers {
+                enemy_amount: 1.7,
+                spawn_rate: 1.6,
+                burst_size: 2.0,
+                enemy_health: 2.0,
+                enemy_speed: 1.25,
+                enemy_damage: 1.5,
+            },
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Difficulty::Story => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Nightmare,
+            Difficulty::Nightmare => Difficulty::Story,
+        }
+    }
+}
+
+impl Difficulty {
+    pub fn key(&self) -> &'static str {
+        match self {
+            Difficulty::Story => "difficulty.story",
+            Difficulty::Normal => "difficulty.normal",
+            Difficulty::Hard => "difficulty.hard",
+            Difficulty::Nightmare => "difficulty.nightmare",
+        }
+    }
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Difficulty::Story => write!(f, "Story"),
+            Difficulty::Normal => write!(f, "Normal"),
+            Difficulty::Hard => write!(f, "Hard"),
+            Difficulty::Nightmare => write!(f, "Nightmare"),
+        }
+    }
+}
 
 #[derive(Resource)]
 pub struct TotalToSpawn {
     pub amount: u32,
     pub rate: f32,
     pub quantity_per_spawn: u32,
+    pub robot_ratio: f32,
+    pub elite_ratio: f32,
 }
 
-impl TotalToSpawn {
-    pub fn update_paramter_for_level_id(&mut self, index: u32) {
-        let (i, multiple) = if index < MAX_WAVE_CALIBRATION {
-            (index as usize, 1)
+// Wave parameters past the calibration tables, every pressure axis grows on its own
+#[derive(Resource, Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EndlessCurve {
+    // Extra enemies per level, as a fraction of the last calibrated wave
+    pub enemy_growth: f32,
+    // Spawn delay is divided by (1 + density_growth * levels), down to min_spawn_delay
+    pub density_growth: f32,
+    pub min_spawn_delay: f32,
+    // Extra mobs per spawn tick for each level
+    pub burst_growth: f32,
+    // Robot and elite ratios ease towards their maximum, the ramp is the number of levels to get ~63% there
+    pub max_robot_ratio: f32,
+    pub robot_ramp: f32,
+    pub max_elite_ratio: f32,
+    pub elite_ramp: f32,
+}
+
+impl Default for EndlessCurve {
+    fn default() -> Self {
+        Self {
+            enemy_growth: ENDLESS_ENEMY_GROWTH,
+            density_growth: ENDLESS_DENSITY_GROWTH,
+            min_spawn_delay: ENDLESS_MIN_SPAWN_DELAY,
+            burst_growth: ENDLESS_BURST_GROWTH,
+            max_robot_ratio: ENDLESS_MAX_ROBOT_RATIO,
+            robot_ramp: ENDLESS_ROBOT_RAMP,
+            max_elite_ratio: ENDLESS_MAX_ELITE_RATIO,
+            elite_ramp: ENDLESS_ELITE_RAMP,
+        }
+    }
+}
+
+impl EndlessCurve {
+    // Ratios stay fractions and every growth keeps the waves from getting easier
+    pub fn problem(&self) -> Option<&'static str> {
+        let ratios = [self.max_robot_ratio, self.max_elite_ratio];
+        if ratios.iter().any(|ratio| !(0.0..=1.0).contains(ratio)) {
+            Some("ratios must be between 0 and 1")
+        } else if self.robot_ramp <= 0.0 || self.elite_ramp <= 0.0 || self.min_spawn_delay <= 0.0 {
+            Some("ramps and the minimum spawn delay must be above 0")
+        } else if [self.enemy_growth, self.density_growth, self.burst_growth]
+            .iter()
+            .any(|growth| *growth < 0.0)
+        {
+            Some("growths cannot be negative")
         } else {
-            (
-                MAX_WAVE_CALIBRATION as usize,
-                ((index + 2) as i32 - MAX_WAVE_CALIBRATION as i32) as u32,
-            )
-        };
-        self.amount = ENEMY_BY_LVL[i] * multiple;
-        self.rate = SPEED_SPAWN_BY_LVL[i] * multiple as f32;
-        self.quantity_per_spawn = SPAWN_TICK_BY_LVL[i] * multiple;
+            None
+        }
+    }
+
+    // Parameters of the wave at index, before the difficulty modifiers
+    pub fn wave_for_level_id(&self, index: u32) -> TotalToSpawn {
+        let last = MAX_WAVE_CALIBRATION as usize;
+        if index <= MAX_WAVE_CALIBRATION {
+            let i = index as usize;
+            return TotalToSpawn {
+                amount: ENEMY_BY_LVL[i],
+                rate: SPEED_SPAWN_BY_LVL[i],
+                quantity_per_spawn: SPAWN_TICK_BY_LVL[i],
+                robot_ratio: MOB_ROBOT_RATIO,
+                elite_ratio: 0.0,
+            };
+        }
+        let levels = (index - MAX_WAVE_CALIBRATION) as f32;
+        TotalToSpawn {
+            amount: (ENEMY_BY_LVL[last] as f32 * (1.0 + self.enemy_growth * levels)).round() as u32,
+            rate: (SPEED_SPAWN_BY_LVL[last] / (1.0 + self.density_growth * levels))
+                .max(self.min_spawn_delay),
+            quantity_per_spawn: SPAWN_TICK_BY_LVL[last] + (self.burst_growth * levels) as u32,
+            robot_ratio: MOB_ROBOT_RATIO
+                + (self.max_robot_ratio - MOB_ROBOT_RATIO) * ease(levels, self.robot_ramp),
+            elite_ratio: self.max_elite_ratio * ease(levels, self.elite_ramp),
+        }
+    }
+}
+
+fn ease(levels: f32, ramp: f32) -> f32 {
+    1.0 - (-levels / ramp).exp()
+}
+
+impl TotalToSpawn {
+    pub fn update_paramter_for_level_id(
+        &mut self,
+        index: u32,
+        difficulty: Difficulty,
+        curve: &EndlessCurve,
+    ) {
+        let wave = curve.wave_for_level_id(index);
+        let modifiers = difficulty.modifiers();
+        self.amount = (wave.amount as f32 * modifiers.enemy_amount).round() as u32;
+        // rate is the delay between two spawns, a faster spawn rate shortens it
+        self.rate = wave.rate / modifiers.spawn_rate;
+        self.quantity_per_spawn =
+            ((wave.quantity_per_spawn as f32 * modifiers.burst_size).round() as u32).max(1);
+        self.robot_ratio = wave.robot_ratio;
+        self.elite_ratio = wave.elite_ratio;
     }
 }
 
 impl Default for TotalToSpawn {
     fn default() -> Self {
-        Self {
-            amount: ENEMY_BY_LVL[0],
-            rate: SPEED_SPAWN_BY_LVL[0],
-            quantity_per_spawn: SPAWN_TICK_BY_LVL[0],
-        }
+        EndlessCurve::default().wave_for_level_id(0)
     }
 }
 
@@ -51,9 +250,99 @@ pub struct TotalKilled {
     pub amount: u32,
 }
 
-#[derive(Resource, Default)]
-pub struct LastShot {
-    pub delta_time: f32,
+// Stats of every player of the run, a single one unless playing co-op
+#[derive(Resource)]
+pub struct Party {
+    pub players: Vec<StatsRes>,
+    // Player choosing a parent on the level menu
+    pub picking: usize,
+    // Every flaw inherited by any player since the start of the run
+    pub flaws: BTreeSet<DebufChoices>,
+}
+
+// How much every flaw weakens the player, mods can make a flaw milder or harsher
+#[derive(Resource, Clone, Debug)]
+pub struct Traits {
+    factors: BTreeMap<DebufChoices, f32>,
+}
+
+impl Default for Traits {
+    fn default() -> Self {
+        Self {
+            factors: BTreeMap::from([
+                (DebufChoices::Speed, FLAW_SPEED),
+                (DebufChoices::Bullets, FLAW_BULLETS),
+                (DebufChoices::BulletsTtl, FLAW_PIERCING),
+                (DebufChoices::Damage, FLAW_DAMAGE),
+                (DebufChoices::BulletsSpeed, FLAW_BULLETS_SPEED),
+                (DebufChoices::FireRate, FLAW_FIRE_RATE),
+            ]),
+        }
+    }
+}
+
+impl Traits {
+    pub fn factor(&self, flaw: DebufChoices) -> f32 {
+        self.factors[&flaw]
+    }
+
+    pub fn set_factor(&mut self, flaw: DebufChoices, factor: f32) {
+        self.factors.insert(flaw, factor);
+    }
+
+    // Percentage the flaw takes off, as the level menu shows it
+    pub fn penalty(&self, flaw: DebufChoices) -> i32 {
+        let factor = self.factor(flaw);
+        let penalty = match flaw {
+            DebufChoices::FireRate => factor - 1.0,
+            _ => 1.0 - factor,
+        };
+        (penalty * 100.0).round() as i32
+    }
+
+    // A flaw has to weaken the player, the fire rate factor stretches the delay between shots
+    pub fn problem(flaw: DebufChoices, factor: f32) -> Option<&'static str> {
+        match flaw {
+            _ if !factor.is_finite() => Some("must be a number"),
+            DebufChoices::FireRate if factor < 1.0 => Some("must be at least 1"),
+            DebufChoices::FireRate => None,
+            _ if factor <= 0.0 || factor > 1.0 => Some("must be above 0 and at most 1"),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Party {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl Party {
+    pub fn new(count: usize) -> Self {
+        Self {
+            players: (0..count).map(|_| StatsRes::default()).collect(),
+            picking: 0,
+            flaws: BTreeSet::new(),
+        }
+    }
+
+    // Weaken a player with the flaws of the chosen parent
+    pub fn inherit(&mut self, player: usize, parent: &Debuff, color: Color, traits: &Traits) {
+        let stats = &mut self.players[player];
+        parent.apply(stats, traits);
+        stats.player_color = color;
+        self.flaws.extend(parent.get_defaults());
+    }
+
+    pub fn count(&self) -> usize {
+        self.players.len()
+    }
+
+    // Fresh stats for a new run with as many players
+    pub fn reset(&mut self) {
+        *self = Self::new(self.count());
+    }
 }
 
 #[derive(Resource)]
@@ -61,9 +350,49 @@ pub struct Score {
     level: u32,
     date_pannel_level_effect: f32,
     date: i32,
+    difficulty: Difficulty,
+    // Wave the run was launched on, past the first one it skipped part of the run
+    start_level: u32,
 }
 
 impl Score {
+    // Fresh run keeping the difficulty picked in the main menu
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self {
+            difficulty,
+            ..default()
+        }
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty;
+    }
+
+    // Run starting straight at the given wave, as if the previous ones were cleared
+    pub fn starting_at(level: u32, difficulty: Difficulty) -> Self {
+        let date = BEGIN_DATE - DECREMENT_DATE_PER_LEVEL * level as i32;
+        Self {
+            level,
+            date,
+            date_pannel_level_effect: date as f32,
+            start_level: level,
+            ..Self::new(difficulty)
+        }
+    }
+
+    // Only runs played from the first wave unlock achievements and go on the high scores
+    pub fn full_run(&self) -> bool {
+        self.start_level == 0
+    }
+
+    pub fn get_date(&self) -> i32 {
+        self.date
+    }
+
     pub fn get_level_index(&self) -> u32 {
         self.level
     }
@@ -81,18 +410,6 @@ impl Score {
     pub fn to_text(&self) -> String {
         format!("{}", self.date_pannel_level_effect.round() as i32)
     }
-
-    pub fn historic_period_theme(&self) -> i32 {
-        match self.level {
-            s if s < 2 => 0,
-            s if s >= 2 && s < 5 => 1,
-            _ => 2,
-        }
-    }
-
-    pub fn should_start_music(&self) -> bool {
-        self.level == 0 || self.level == 2 || self.level == 5
-    }
 }
 
 impl Default for Score {
@@ -101,15 +418,1616 @@ impl Default for Score {
             level: 0,
             date_pannel_level_effect: BEGIN_DATE as f32,
             date: BEGIN_DATE,
+            difficulty: Difficulty::default(),
+            start_level: 0,
         }
     }
 }
 
-pub enum ChunkType {
-    Basic,
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HighScoreEntry {
+    pub date: i32,
+    pub level: u32,
+    pub difficulty: Difficulty,
+}
+
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    pub entries: Vec<HighScoreEntry>,
+}
+
+impl HighScores {
+    pub fn load() -> Self {
+        save::load_or_default(HIGH_SCORES_FILE)
+    }
+
+    pub fn save(&self) {
+        save::save(HIGH_SCORES_FILE, self);
+    }
+
+    // The oldest date reached is the best score
+    pub fn insert(&mut self, entry: HighScoreEntry) {
+        self.entries.push(entry);
+        self.entries.sort_by_key(|entry| entry.date);
+        self.entries.truncate(MAX_HIGH_SCORES);
+    }
 }
 
+// Daily Ancestry run, everyone playing on the same day gets the same spawns and parents
 #[derive(Resource)]
-pub struct ChunksMap {
-    pub chunks: HashMap<(i32, i32), ChunkType>,
+pub struct DailyRun {
+    // Days since 1970-01-01, in UTC
+    pub day: u64,
+    // Only the first attempt of the day goes on the daily leaderboard
+    pub scored: bool,
+    // Main menu selections put aside for the run, given back once it ends
+    pub menu_players: usize,
+    pub menu_difficulty: Difficulty,
+}
+
+impl DailyRun {
+    pub fn today() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() / SECONDS_PER_DAY)
+    }
+
+    // Each wave gets its own seed, so how the previous ones went changes nothing
+    pub fn wave_seed(&self, level: u32) -> u64 {
+        (self.day << 32) | ((level as u64) << 1)
+    }
+
+    // Parents are drawn apart from the spawns of the wave they follow
+    pub fn parents_seed(&self, level: u32) -> u64 {
+        self.wave_seed(level) | 1
+    }
+}
+
+// Year-month-day of a day count, in the proleptic Gregorian calendar
+pub fn calendar_date(day: u64) -> String {
+    let days = day as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Months counted from March, so the leap day ends the year
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day_of_month = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day_of_month:02}")
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DailyScoreEntry {
+    pub day: u64,
+    pub date: i32,
+    pub level: u32,
+}
+
+#[derive(Resource, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyScores {
+    pub entries: Vec<DailyScoreEntry>,
+    // Day of the last scored attempt
+    pub last_attempt: Option<u64>,
+}
+
+impl DailyScores {
+    pub fn load() -> Self {
+        save::load_or_default(DAILY_SCORES_FILE)
+    }
+
+    pub fn save(&self) {
+        save::save(DAILY_SCORES_FILE, self);
+    }
+
+    pub fn attempted(&self, day: u64) -> bool {
+        self.last_attempt == Some(day)
+    }
+
+    // The oldest date reached is the best score, like the high scores
+    pub fn insert(&mut self, entry: DailyScoreEntry) {
+        self.entries.push(entry);
+        self.entries.sort_by_key(|entry| entry.date);
+        self.entries.truncate(MAX_DAILY_SCORES);
+    }
+}
+
+// Every random roll of a run goes through this generator so a seed replays the same run
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameRng(pub StdRng);
+
+impl GameRng {
+    pub fn new(seed: Option<u64>) -> Self {
+        match seed {
+            Some(seed) => Self(StdRng::seed_from_u64(seed)),
+            None => Self(StdRng::from_entropy()),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct MusicChannel;
+
+#[derive(Resource)]
+pub struct SfxChannel;
+
+#[derive(Resource)]
+pub struct UiChannel;
+
+// Music currently handled by the director, kept across screens to crossfade between tracks
+#[derive(Resource)]
+pub struct MusicDirector {
+    pub track: Option<Handle<AudioSource>>,
+    pub instance: Option<Handle<AudioInstance>>,
+    pub playback_rate: f64,
+}
+
+impl Default for MusicDirector {
+    fn default() -> Self {
+        Self {
+            track: None,
+            instance: None,
+            playback_rate: 1.0,
+        }
+    }
+}
+
+// Sound effects still playing on the SFX channel, used to cap the concurrent voices
+#[derive(Resource, Default)]
+pub struct SfxVoices {
+    pub instances: Vec<Handle<AudioInstance>>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct ChannelSettings {
+    pub volume: f32,
+    pub muted: bool,
+}
+
+impl ChannelSettings {
+    pub fn effective_volume(&self) -> f64 {
+        if self.muted {
+            0.0
+        } else {
+            self.volume as f64
+        }
+    }
+
+    // A hand edited volume never plays louder than full, one that is not a number is reset
+    pub fn clamp_volume(&mut self) {
+        self.volume = if self.volume.is_finite() {
+            self.volume.clamp(0.0, 1.0)
+        } else {
+            DEFAULT_VOLUME
+        };
+    }
+
+    pub fn step_volume(&mut self, step: f32) {
+        self.volume = ((self.volume + step) * 10.0).round().clamp(0.0, 10.0) / 10.0;
+    }
+}
+
+impl Default for ChannelSettings {
+    fn default() -> Self {
+        Self {
+            volume: DEFAULT_VOLUME,
+            muted: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SettingsChannel {
+    Music,
+    Sfx,
+    Ui,
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub music: ChannelSettings,
+    pub sfx: ChannelSettings,
+    pub ui: ChannelSettings,
+}
+
+impl AudioSettings {
+    pub fn channel(&self, channel: SettingsChannel) -> &ChannelSettings {
+        match channel {
+            SettingsChannel::Music => &self.music,
+            SettingsChannel::Sfx => &self.sfx,
+            SettingsChannel::Ui => &self.ui,
+        }
+    }
+
+    pub fn channel_mut(&mut self, channel: SettingsChannel) -> &mut ChannelSettings {
+        match channel {
+            SettingsChannel::Music => &mut self.music,
+            SettingsChannel::Sfx => &mut self.sfx,
+            SettingsChannel::Ui => &mut self.ui,
+        }
+    }
+}
+
+// Player preferences, persisted between sessions
+#[derive(Resource, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub audio: AudioSettings,
+    pub language: Language,
+    pub accessibility: AccessibilitySettings,
+    pub hud: HudSettings,
+    // Mod packs left out of the next launch, by folder name
+    pub disabled_mods: BTreeSet<String>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HudSettings {
+    pub minimap: bool,
+    pub damage_numbers: bool,
+}
+
+impl Default for HudSettings {
+    fn default() -> Self {
+        Self {
+            minimap: true,
+            damage_numbers: true,
+        }
+    }
+}
+
+impl Settings {
+    // Hand edited values are brought back to the ones the menus offer
+    pub fn load() -> Self {
+        let mut settings: Self = save::load_or_default(SETTINGS_FILE);
+        for channel in [
+            SettingsChannel::Music,
+            SettingsChannel::Sfx,
+            SettingsChannel::Ui,
+        ] {
+            settings.audio.channel_mut(channel).clamp_volume();
+        }
+        settings.accessibility.snap_text_scale();
+        settings
+    }
+
+    pub fn mod_enabled(&self, id: &str) -> bool {
+        !self.disabled_mods.contains(id)
+    }
+
+    pub fn save(&self) {
+        save::save(SETTINGS_FILE, self);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Palette {
+    #[default]
+    Standard,
+    // Safe for deuteranopia and protanopia
+    RedGreen,
+    // Safe for tritanopia
+    BlueYellow,
+}
+
+impl Palette {
+    pub fn next(&self) -> Self {
+        match self {
+            Palette::Standard => Palette::RedGreen,
+            Palette::RedGreen => Palette::BlueYellow,
+            Palette::BlueYellow => Palette::Standard,
+        }
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Palette::Standard => "palette.standard",
+            Palette::RedGreen => "palette.red_green",
+            Palette::BlueYellow => "palette.blue_yellow",
+        }
+    }
+
+    // Color of an enemy about to die
+    pub fn hurt_color(&self) -> Color {
+        match self {
+            Palette::Standard => MOB_COLOR_HURT,
+            Palette::RedGreen => MOB_COLOR_HURT_RED_GREEN,
+            Palette::BlueYellow => MOB_COLOR_HURT_BLUE_YELLOW,
+        }
+    }
+
+    // Dad and mom colors replacing the random tints, `None` keeps the random ones
+    pub fn parent_colors(&self) -> Option<(Color, Color)> {
+        match self {
+            Palette::Standard => None,
+            Palette::RedGreen => Some(PARENT_COLORS_RED_GREEN),
+            Palette::BlueYellow => Some(PARENT_COLORS_BLUE_YELLOW),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum FireMode {
+    // Fire while the button is held
+    #[default]
+    Hold,
+    // A click starts firing and the next one stops
+    Toggle,
+}
+
+impl FireMode {
+    pub fn next(&self) -> Self {
+        match self {
+            FireMode::Hold => FireMode::Toggle,
+            FireMode::Toggle => FireMode::Hold,
+        }
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            FireMode::Hold => "fire_mode.hold",
+            FireMode::Toggle => "fire_mode.toggle",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessibilitySettings {
+    pub palette: Palette,
+    pub text_scale: f32,
+    // No sliding panels nor screen shake
+    pub reduce_motion: bool,
+    pub fire_mode: FireMode,
+}
+
+impl Default for AccessibilitySettings {
+    fn default() -> Self {
+        Self {
+            palette: Palette::default(),
+            text_scale: 1.0,
+            reduce_motion: false,
+            fire_mode: FireMode::default(),
+        }
+    }
+}
+
+impl AccessibilitySettings {
+    // Closest scale offered by the menu, a hand edited one that is not a number counts as 1
+    fn text_scale_index(&self) -> usize {
+        let text_scale = if self.text_scale.is_finite() {
+            self.text_scale
+        } else {
+            1.0
+        };
+        (0..TEXT_SCALES.len())
+            .min_by(|a, b| {
+                let distance = |index: &usize| (TEXT_SCALES[*index] - text_scale).abs();
+                distance(a).total_cmp(&distance(b))
+            })
+            .unwrap_or(0)
+    }
+
+    pub fn snap_text_scale(&mut self) {
+        self.text_scale = TEXT_SCALES[self.text_scale_index()];
+    }
+
+    pub fn next_text_scale(&mut self) {
+        self.text_scale = TEXT_SCALES[(self.text_scale_index() + 1) % TEXT_SCALES.len()];
+    }
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum Language {
+    #[default]
+    English,
+    French,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::French];
+
+    pub fn next(&self) -> Self {
+        match self {
+            Language::English => Language::French,
+            Language::French => Language::English,
+        }
+    }
+
+    // Shipped inside the binary so the web build does not need file access
+    fn strings_file(&self) -> &'static str {
+        match self {
+            Language::English => include_str!("../assets/locales/en.ron"),
+            Language::French => include_str!("../assets/locales/fr.ron"),
+        }
+    }
+}
+
+// Player-facing text of every language, looked up by key
+#[derive(Resource)]
+pub struct Localization {
+    pub language: Language,
+    strings: HashMap<Language, HashMap<String, String>>,
+}
+
+impl Localization {
+    pub fn new(language: Language) -> Self {
+        let strings = Language::ALL
+            .iter()
+            .map(|language| {
+                let strings = ron::from_str(language.strings_file()).unwrap_or_else(|error| {
+                    warn!("Could not read the {language:?} strings: {error}");
+                    HashMap::new()
+                });
+                (*language, strings)
+            })
+            .collect();
+        let localization = Self { language, strings };
+        for (language, key) in localization.missing_keys() {
+            warn!("Missing {language:?} string \"{key}\", English is shown instead");
+        }
+        localization
+    }
+
+    fn lookup(&self, language: Language, key: &str) -> Option<&str> {
+        self.strings
+            .get(&language)
+            .and_then(|strings| strings.get(key))
+            .map(String::as_str)
+    }
+
+    // Falls back to English, then to the key itself so a missing string is easy to spot
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.lookup(self.language, key)
+            .or_else(|| self.lookup(Language::English, key))
+            .unwrap_or(key)
+    }
+
+    // Replace every `{name}` of the string by its value
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        args.iter()
+            .fold(self.get(key).to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{name}}}"), &value.to_string())
+            })
+    }
+
+    // Name of a language written in that language
+    pub fn language_name(&self, language: Language) -> &str {
+        self.lookup(language, "language.name").unwrap_or("?")
+    }
+
+    // Keys some language has and another one lacks
+    pub fn missing_keys(&self) -> Vec<(Language, String)> {
+        let mut missing: Vec<(Language, String)> = vec![];
+        for (language, strings) in &self.strings {
+            for (other, other_strings) in &self.strings {
+                if language == other {
+                    continue;
+                }
+                missing.extend(
+                    other_strings
+                        .keys()
+                        .filter(|key| !strings.contains_key(*key))
+                        .map(|key| (*language, key.clone())),
+                );
+            }
+        }
+        missing.sort();
+        missing.dedup();
+        missing
+    }
+}
+
+pub enum ChunkType {
+    Basic,
+}
+
+#[derive(Resource)]
+pub struct ChunksMap {
+    pub chunks: HashMap<(i32, i32), ChunkType>,
+}
+
+// Simulated or demo games must not overwrite the player's saves
+#[derive(Resource)]
+pub struct Persistence {
+    pub enabled: bool,
+}
+
+impl Default for Persistence {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BotMovement {
+    KiteCircles,
+    StandStill,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BotParents {
+    Random,
+    // Always pick the parent that does not pass this flaw down
+    Avoid(DebufChoices),
+}
+
+// Scripted player taking the place of the keyboard and mouse
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct Bot {
+    pub movement: BotMovement,
+    pub parents: BotParents,
+    // The run is stopped once this wave is reached, in case the bot never dies
+    pub max_level: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BotRunRecord {
+    pub level: u32,
+    pub date: i32,
+    // Stopped at the level cap instead of dying
+    pub survived: bool,
+    // Seconds spent in every cleared wave, in order
+    pub wave_seconds: Vec<f32>,
+    // Flaws inherited after every cleared wave, in order
+    pub debuffs: Vec<[DebufChoices; 3]>,
+}
+
+// Everything a demo game could touch, put aside until the demo is over
+pub struct RunSnapshot {
+    pub score: Score,
+    pub party: Party,
+    pub total_to_spawn: TotalToSpawn,
+    pub total_killed: TotalKilled,
+    pub total_spawned: TotalSpawned,
+    pub rng: GameRng,
+    pub persistence: bool,
+}
+
+#[derive(Resource)]
+pub struct AttractMode {
+    // Time without input on the main menu before the demo starts
+    pub idle: Timer,
+    // The player's run while a demo is playing
+    pub saved_run: Option<RunSnapshot>,
+}
+
+impl Default for AttractMode {
+    fn default() -> Self {
+        Self {
+            idle: Timer::from_seconds(ATTRACT_IDLE_SECONDS, TimerMode::Once),
+            saved_run: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipName {
+    Idle,
+    RunDown,
+    RunUp,
+    Hurt,
+    Die,
+}
+
+impl ClipName {
+    pub const ALL: [ClipName; 5] = [
+        ClipName::Idle,
+        ClipName::RunDown,
+        ClipName::RunUp,
+        ClipName::Hurt,
+        ClipName::Die,
+    ];
+}
+
+// When a clip mirrors the sprite, the sheets are drawn facing a single side
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlipRule {
+    Never,
+    MovingLeft,
+    MovingRight,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct AnimationClip {
+    // Atlas indices of the first and last frames, both included
+    pub first: usize,
+    pub last: usize,
+    pub frame_seconds: f32,
+    // Clips played once hold their last frame
+    pub looping: bool,
+    pub flip: FlipRule,
+}
+
+impl AnimationClip {
+    pub fn len(&self) -> usize {
+        self.last.saturating_sub(self.first) + 1
+    }
+}
+
+// Shown when a sheet misses a clip
+const STILL_CLIP: AnimationClip = AnimationClip {
+    first: 0,
+    last: 0,
+    frame_seconds: 0.1,
+    looping: true,
+    flip: FlipRule::Never,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AnimationSheet {
+    Player,
+    Mob,
+}
+
+impl AnimationSheet {
+    pub const ALL: [AnimationSheet; 2] = [AnimationSheet::Player, AnimationSheet::Mob];
+
+    fn clips_file(&self) -> &'static str {
+        match self {
+            AnimationSheet::Player => include_str!("../assets/animations/player.ron"),
+            AnimationSheet::Mob => include_str!("../assets/animations/mob.ron"),
+        }
+    }
+
+    // Columns and rows of 64 pixels squares in the atlas
+    pub fn grid(&self) -> (usize, usize) {
+        match self {
+            AnimationSheet::Player => (6, 2),
+            AnimationSheet::Mob => (5, 1),
+        }
+    }
+
+    // Frames in the atlas the clips index into
+    pub fn frames(&self) -> usize {
+        let (columns, rows) = self.grid();
+        columns * rows
+    }
+
+    pub fn atlas(&self, texture: Handle<Image>) -> TextureAtlas {
+        let (columns, rows) = self.grid();
+        TextureAtlas::from_grid(texture, Vec2::new(64.0, 64.0), columns, rows, None, None)
+    }
+}
+
+// What has to hold for a story beat to play, the conditions left out always hold
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StoryCondition {
+    pub min_level: Option<u32>,
+    pub max_level: Option<u32>,
+    // Index of the era in the order they start
+    pub era: Option<usize>,
+    // Only right before the first wave of an era
+    pub era_start: bool,
+    // Flaws that must all have been inherited during the run
+    pub flaws: Vec<DebufChoices>,
+    // Runs lost over every session
+    pub min_deaths: Option<u32>,
+    pub max_deaths: Option<u32>,
+}
+
+// Where the run stands when the level menu opens
+pub struct StoryMoment<'a> {
+    pub level: u32,
+    pub era: usize,
+    pub era_start: bool,
+    pub flaws: &'a BTreeSet<DebufChoices>,
+    pub deaths: u32,
+}
+
+impl StoryCondition {
+    pub fn holds(&self, moment: &StoryMoment) -> bool {
+        self.min_level.is_none_or(|min| moment.level >= min)
+            && self.max_level.is_none_or(|max| moment.level <= max)
+            && self.era.is_none_or(|era| moment.era == era)
+            && (!self.era_start || moment.era_start)
+            && self.flaws.iter().all(|flaw| moment.flaws.contains(flaw))
+            && self.min_deaths.is_none_or(|min| moment.deaths >= min)
+            && self.max_deaths.is_none_or(|max| moment.deaths <= max)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DialogueLine {
+    pub speaker: String,
+    #[serde(default)]
+    pub portrait: Option<String>,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StoryBeat {
+    pub id: String,
+    #[serde(default)]
+    pub when: StoryCondition,
+    // Never played again once seen, in any later run
+    #[serde(default)]
+    pub once: bool,
+    pub lines: Vec<DialogueLine>,
+}
+
+// Dialogues played between waves, in the order of the story file
+#[derive(Resource)]
+pub struct Story {
+    beats: Vec<StoryBeat>,
+}
+
+impl Default for Story {
+    fn default() -> Self {
+        let beats =
+            ron::from_str(include_str!("../assets/story/story.ron")).unwrap_or_else(|error| {
+                warn!("Could not read the story: {error}");
+                vec![]
+            });
+        Self { beats }
+    }
+}
+
+impl Story {
+    // Images shown next to the lines, loaded with the other assets
+    pub fn portraits(&self) -> impl Iterator<Item = &String> {
+        self.beats
+            .iter()
+            .flat_map(|beat| &beat.lines)
+            .filter_map(|line| line.portrait.as_ref())
+    }
+
+    // Mod beats come after the shipped ones, which keep the priority
+    pub fn extend(&mut self, beats: Vec<StoryBeat>) {
+        self.beats.extend(beats);
+    }
+
+    pub fn has_beat(&self, id: &str) -> bool {
+        self.beats.iter().any(|beat| beat.id == id)
+    }
+
+    pub fn next_beat(&self, moment: &StoryMoment, seen: &BTreeSet<String>) -> Option<&StoryBeat> {
+        self.beats
+            .iter()
+            .filter(|beat| !beat.lines.is_empty())
+            .find(|beat| !(beat.once && seen.contains(&beat.id)) && beat.when.holds(moment))
+    }
+}
+
+// Story state kept from a session to the next
+#[derive(Resource, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StoryProgress {
+    pub deaths: u32,
+    // Beats played once, by id
+    pub seen: BTreeSet<String>,
+}
+
+impl StoryProgress {
+    pub fn load() -> Self {
+        save::load_or_default(STORY_FILE)
+    }
+
+    pub fn save(&self) {
+        save::save(STORY_FILE, self);
+    }
+}
+
+// Unset fields always hold, an achievement unlocks once all the set ones do
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AchievementCondition {
+    // Waves cleared during the run
+    pub min_level: Option<u32>,
+    // Year the run went back to, or further
+    pub year: Option<i32>,
+    // Only on clearing a wave, with no player firing more bullets at once
+    pub max_bullets: Option<u32>,
+    // Flaws that must all have been inherited during the run
+    pub flaws: Vec<DebufChoices>,
+    pub difficulty: Option<Difficulty>,
+}
+
+// Where the run stands when achievements are checked
+pub struct AchievementMoment<'a> {
+    pub level: u32,
+    pub year: i32,
+    // Most bullets a player fired at once, when a wave was just cleared
+    pub bullets: Option<u32>,
+    pub flaws: &'a BTreeSet<DebufChoices>,
+    pub difficulty: Difficulty,
+}
+
+impl AchievementCondition {
+    pub fn holds(&self, moment: &AchievementMoment) -> bool {
+        self.min_level.is_none_or(|min| moment.level >= min)
+            && self.year.is_none_or(|year| moment.year <= year)
+            && self
+                .max_bullets
+                .is_none_or(|max| moment.bullets.is_some_and(|bullets| bullets <= max))
+            && self.flaws.iter().all(|flaw| moment.flaws.contains(flaw))
+            && self
+                .difficulty
+                .is_none_or(|difficulty| moment.difficulty == difficulty)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Achievement {
+    pub id: String,
+    // Localization keys
+    pub name: String,
+    pub description: String,
+    pub when: AchievementCondition,
+}
+
+// Long-term goals, in the order of the achievements file
+#[derive(Resource)]
+pub struct Achievements {
+    pub list: Vec<Achievement>,
+}
+
+impl Default for Achievements {
+    fn default() -> Self {
+        let list = ron::from_str(include_str!("../assets/achievements/achievements.ron"))
+            .unwrap_or_else(|error| {
+                warn!("Could not read the achievements: {error}");
+                vec![]
+            });
+        Self { list }
+    }
+}
+
+impl Achievements {
+    // Indexes of the achievements the moment unlocks for the first time
+    pub fn unlocked_by(
+        &self,
+        moment: &AchievementMoment,
+        unlocked: &BTreeSet<String>,
+    ) -> Vec<usize> {
+        self.list
+            .iter()
+            .enumerate()
+            .filter(|(_, achievement)| {
+                !unlocked.contains(&achievement.id) && achievement.when.holds(moment)
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+// Achievements unlocked over every session, by id
+#[derive(Resource, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AchievementProgress {
+    pub unlocked: BTreeSet<String>,
+}
+
+impl AchievementProgress {
+    pub fn load() -> Self {
+        save::load_or_default(ACHIEVEMENTS_FILE)
+    }
+
+    pub fn save(&self) {
+        save::save(ACHIEVEMENTS_FILE, self);
+    }
+}
+
+// Where an era begins, a date starts it on the first wave reaching that year
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum EraStart {
+    Level(u32),
+    Date(i32),
+}
+
+impl EraStart {
+    pub fn first_level(&self) -> u32 {
+        match *self {
+            EraStart::Level(level) => level,
+            EraStart::Date(date) => {
+                ((BEGIN_DATE - date).max(0) as u32).div_ceil(DECREMENT_DATE_PER_LEVEL as u32)
+            }
+        }
+    }
+}
+
+fn default_multiplier() -> f32 {
+    1.0
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnemyKind {
+    pub atlas: String,
+    // Applied on top of the regular mob health and speed
+    #[serde(default = "default_multiplier")]
+    pub health: f32,
+    #[serde(default = "default_multiplier")]
+    pub speed: f32,
+}
+
+// A historic period of the run, from its first wave until the next era starts
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Era {
+    // Localization keys, shown as written when no language has them
+    pub name: String,
+    pub lore: String,
+    pub start: EraStart,
+    // Map chunks are tiled with these images
+    pub chunk_tiles: Vec<String>,
+    pub music: String,
+    // Enemies born in the era and the robots sent back from the future
+    pub natives: Vec<EnemyKind>,
+    pub robots: Vec<EnemyKind>,
+    pub tint: (f32, f32, f32),
+}
+
+impl Era {
+    pub fn tint(&self) -> Color {
+        Color::rgb(self.tint.0, self.tint.1, self.tint.2)
+    }
+
+    pub fn problem(&self) -> Option<&'static str> {
+        if self.chunk_tiles.is_empty() {
+            Some("has no chunk tiles")
+        } else if self.natives.is_empty() && self.robots.is_empty() {
+            Some("has no enemies")
+        } else {
+            None
+        }
+    }
+}
+
+// Folder holding the assets and the mods, the one the AssetServer loads from wherever the game is
+// launched
+pub fn base_folder() -> PathBuf {
+    FileAssetIo::get_base_path()
+}
+
+// Shipped eras, used when the eras folder cannot be read
+const BUILTIN_ERAS: [(&str, &str); 3] = [
+    ("0_future.ron", include_str!("../assets/eras/0_future.ron")),
+    ("1_modern.ron", include_str!("../assets/eras/1_modern.ron")),
+    (
+        "2_old_times.ron",
+        include_str!("../assets/eras/2_old_times.ron"),
+    ),
+];
+
+// Historic periods a run goes back through, sorted by their first wave
+#[derive(Resource)]
+pub struct Eras {
+    eras: Vec<Era>,
+}
+
+impl Default for Eras {
+    fn default() -> Self {
+        let mut files: Vec<(String, String)> = fs::read_dir(base_folder().join(ERAS_FOLDER))
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+                    .filter_map(|path| {
+                        let content = fs::read_to_string(&path).ok()?;
+                        Some((path.display().to_string(), content))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        files.sort();
+        Self::from_files(&files)
+    }
+}
+
+impl Eras {
+    pub fn builtin() -> Self {
+        let files: Vec<(String, String)> = BUILTIN_ERAS
+            .iter()
+            .map(|(name, content)| (name.to_string(), content.to_string()))
+            .collect();
+        Self {
+            eras: Self::parse(&files),
+        }
+    }
+
+    // Broken definitions are left out, the run goes on with the other eras or the shipped ones
+    // when none is left
+    pub fn from_files(files: &[(String, String)]) -> Self {
+        let eras = Self::parse(files);
+        if eras.is_empty() {
+            warn!("No era could be read, using the shipped ones");
+            return Self::builtin();
+        }
+        Self { eras }
+    }
+
+    fn parse(files: &[(String, String)]) -> Vec<Era> {
+        let mut eras: Vec<Era> = files
+            .iter()
+            .filter_map(|(name, content)| match ron::from_str::<Era>(content) {
+                Ok(era) => match era.problem() {
+                    Some(problem) => {
+                        warn!("Ignoring era {name}: it {problem}");
+                        None
+                    }
+                    None => Some(era),
+                },
+                Err(error) => {
+                    warn!("Ignoring era {name}: {error}");
+                    None
+                }
+            })
+            .collect();
+        eras.sort_by_key(|era| era.start.first_level());
+        eras
+    }
+
+    pub fn all(&self) -> &[Era] {
+        &self.eras
+    }
+
+    // An era starting on the same wave as another one takes its place
+    pub fn extend(&mut self, eras: Vec<Era>) {
+        self.eras.retain(|era| {
+            !eras
+                .iter()
+                .any(|new| new.start.first_level() == era.start.first_level())
+        });
+        self.eras.extend(eras);
+        self.eras.sort_by_key(|era| era.start.first_level());
+    }
+
+    pub fn has_era(&self, name: &str) -> bool {
+        self.eras.iter().any(|era| era.name == name)
+    }
+
+    // Extra enemy for every era with this name
+    pub fn add_enemy(&mut self, era_name: &str, robot: bool, kind: EnemyKind) {
+        for era in self.eras.iter_mut().filter(|era| era.name == era_name) {
+            if robot {
+                era.robots.push(kind.clone());
+            } else {
+                era.natives.push(kind.clone());
+            }
+        }
+    }
+
+    // Index of the era a wave takes place in, waves before the first era belong to it
+    pub fn index_at(&self, level: u32) -> usize {
+        self.eras
+            .iter()
+            .rposition(|era| era.start.first_level() <= level)
+            .unwrap_or(0)
+    }
+
+    pub fn at(&self, level: u32) -> &Era {
+        &self.eras[self.index_at(level)]
+    }
+
+    pub fn starts_era(&self, level: u32) -> bool {
+        level == 0 || self.eras.iter().any(|era| era.start.first_level() == level)
+    }
+}
+
+// What an era draws and plays, with handles in place of the file paths
+pub struct EraAssets {
+    pub first_level: u32,
+    pub chunk_tiles: Vec<Handle<Image>>,
+    pub tint: Color,
+    pub music: Handle<AudioSource>,
+    pub natives: Vec<(EnemyKind, Handle<TextureAtlas>)>,
+    pub robots: Vec<(EnemyKind, Handle<TextureAtlas>)>,
+}
+
+impl EraAssets {
+    // Robots are picked from the natives if the era has none, and the other way around
+    pub fn roster(&self, robot: bool) -> &[(EnemyKind, Handle<TextureAtlas>)] {
+        match (robot, self.robots.is_empty(), self.natives.is_empty()) {
+            (true, false, _) | (false, _, true) => &self.robots,
+            _ => &self.natives,
+        }
+    }
+
+    // Tiles are spread over the map the same way on every run
+    pub fn chunk_tile(&self, i: i32, j: i32) -> Handle<Image> {
+        let index = (i.wrapping_mul(73_856_093) ^ j.wrapping_mul(19_349_663)).unsigned_abs()
+            as usize
+            % self.chunk_tiles.len();
+        self.chunk_tiles[index].clone()
+    }
+}
+
+// Every file the game shows or plays, requested once at startup so nothing is loaded in the
+// middle of a run
+#[derive(Resource)]
+pub struct GameAssets {
+    pub font: Handle<Font>,
+    pub title_screen: Handle<Image>,
+    pub parent_sprite: Handle<Image>,
+    pub player_atlas: Handle<TextureAtlas>,
+    pub eras: Vec<EraAssets>,
+    // Story portraits by path
+    pub portraits: HashMap<String, Handle<Image>>,
+    pub theme_music: Handle<AudioSource>,
+    pub hit_sound: Handle<AudioSource>,
+    // Every handle above, to follow the loading
+    files: Vec<HandleUntyped>,
+}
+
+impl FromWorld for GameAssets {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>().clone();
+        let mut files = Vec::new();
+        let mut load = |path: String| {
+            let handle = asset_server.load_untyped(path.as_str());
+            files.push(handle.clone());
+            handle
+        };
+        let font = load(UI_FONT.to_string()).typed();
+        let title_screen = load("images/title_screen.png".to_string()).typed();
+        let parent_sprite = load("images/sprite.png".to_string()).typed();
+        let player_image = load("images/atlas.png".to_string()).typed();
+        let theme_music = load("sounds/theme.ogg".to_string()).typed();
+        let hit_sound = load("sounds/hit.ogg".to_string()).typed();
+
+        let portraits = world
+            .resource::<Story>()
+            .portraits()
+            .map(|path| (path.clone(), load(path.clone()).typed()))
+            .collect();
+        // Mob images are loaded with the rest, their atlases are made once every file is requested
+        type MobImages = Vec<(EnemyKind, Handle<Image>)>;
+        let eras: Vec<(EraAssets, MobImages, MobImages)> = world
+            .resource::<Eras>()
+            .all()
+            .iter()
+            .map(|era| {
+                let assets = EraAssets {
+                    first_level: era.start.first_level(),
+                    chunk_tiles: era
+                        .chunk_tiles
+                        .iter()
+                        .map(|path| load(path.clone()).typed())
+                        .collect(),
+                    tint: era.tint(),
+                    music: load(era.music.clone()).typed(),
+                    natives: vec![],
+                    robots: vec![],
+                };
+                let mut images = |kinds: &[EnemyKind]| {
+                    kinds
+                        .iter()
+                        .map(|kind| (kind.clone(), load(kind.atlas.clone()).typed()))
+                        .collect()
+                };
+                (assets, images(&era.natives), images(&era.robots))
+            })
+            .collect();
+
+        let mut texture_atlases = world.resource_mut::<Assets<TextureAtlas>>();
+        let mut mob_atlases = |images: MobImages| {
+            images
+                .into_iter()
+                .map(|(kind, image)| (kind, texture_atlases.add(AnimationSheet::Mob.atlas(image))))
+                .collect()
+        };
+        let eras = eras
+            .into_iter()
+            .map(|(assets, natives, robots)| EraAssets {
+                natives: mob_atlases(natives),
+                robots: mob_atlases(robots),
+                ..assets
+            })
+            .collect();
+        let player_atlas = texture_atlases.add(AnimationSheet::Player.atlas(player_image));
+        Self {
+            font,
+            title_screen,
+            parent_sprite,
+            player_atlas,
+            eras,
+            portraits,
+            theme_music,
+            hit_sound,
+            files,
+        }
+    }
+}
+
+impl GameAssets {
+    pub fn files(&self) -> &[HandleUntyped] {
+        &self.files
+    }
+
+    // Assets of the era a wave takes place in
+    pub fn era_at(&self, level: u32) -> &EraAssets {
+        let index = self
+            .eras
+            .iter()
+            .rposition(|era| era.first_level <= level)
+            .unwrap_or(0);
+        &self.eras[index]
+    }
+}
+
+// Animation clips of every sprite sheet, looked up by name
+#[derive(Resource)]
+pub struct AnimationLibrary {
+    sheets: HashMap<AnimationSheet, HashMap<ClipName, AnimationClip>>,
+}
+
+impl Default for AnimationLibrary {
+    fn default() -> Self {
+        let sheets = AnimationSheet::ALL
+            .iter()
+            .map(|sheet| {
+                let clips: HashMap<ClipName, AnimationClip> = ron::from_str(sheet.clips_file())
+                    .unwrap_or_else(|error| {
+                        warn!("Could not read the {sheet:?} animations: {error}");
+                        HashMap::new()
+                    });
+                for name in ClipName::ALL {
+                    match clips.get(&name) {
+                        None => warn!("The {sheet:?} animations have no {name:?} clip"),
+                        Some(clip) if clip.first > clip.last || clip.last >= sheet.frames() => {
+                            warn!("The {sheet:?} {name:?} clip runs outside of its atlas")
+                        }
+                        _ => {}
+                    }
+                }
+                (*sheet, clips)
+            })
+            .collect();
+        Self { sheets }
+    }
+}
+
+impl AnimationLibrary {
+    // A missing clip falls back to the idle one, then to the first frame
+    pub fn clip(&self, sheet: AnimationSheet, name: ClipName) -> AnimationClip {
+        let clips = &self.sheets[&sheet];
+        clips
+            .get(&name)
+            .or_else(|| clips.get(&ClipName::Idle))
+            .copied()
+            .unwrap_or(STILL_CLIP)
+    }
+}
+
+// Off-screen indicators and the minimap are only redrawn this often
+#[derive(Resource, Deref, DerefMut)]
+pub struct RadarRefresh(pub Timer);
+
+impl Default for RadarRefresh {
+    fn default() -> Self {
+        Self(Timer::from_seconds(
+            RADAR_REFRESH_SECONDS,
+            TimerMode::Repeating,
+        ))
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct BotLog {
+    pub runs: Vec<BotRunRecord>,
+    pub current: BotRunRecord,
+    pub wave_started: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_saved_before_accessibility_still_load() {
+        let settings: Settings = ron::from_str("(language: French)").unwrap();
+        assert_eq!(settings.language, Language::French);
+        assert_eq!(settings.accessibility.palette, Palette::Standard);
+        assert_eq!(settings.accessibility.text_scale, 1.0);
+        assert_eq!(settings.accessibility.fire_mode, FireMode::Hold);
+    }
+
+    #[test]
+    fn hand_edited_volumes_stay_in_range() {
+        let volume = |volume: f32| {
+            let mut channel = ChannelSettings {
+                volume,
+                muted: false,
+            };
+            channel.clamp_volume();
+            channel.effective_volume()
+        };
+        assert_eq!(volume(40.0), 1.0);
+        assert_eq!(volume(-1.0), 0.0);
+        assert_eq!(volume(f32::NAN), DEFAULT_VOLUME as f64);
+        assert_eq!(volume(0.5), 0.5);
+    }
+
+    #[test]
+    fn text_scale_cycles_and_snaps_to_the_offered_ones() {
+        let mut accessibility = AccessibilitySettings::default();
+        let scales: Vec<f32> = (0..4)
+            .map(|_| {
+                accessibility.next_text_scale();
+                accessibility.text_scale
+            })
+            .collect();
+        assert_eq!(scales, vec![1.25, 1.5, 0.75, 1.0]);
+        let snapped: Vec<f32> = [0.0, 1.1, 50.0, f32::NAN]
+            .into_iter()
+            .map(|text_scale| {
+                accessibility.text_scale = text_scale;
+                accessibility.snap_text_scale();
+                accessibility.text_scale
+            })
+            .collect();
+        assert_eq!(snapped, vec![0.75, 1.0, 1.5, 1.0]);
+    }
+
+    #[test]
+    fn every_language_has_every_key() {
+        let localization = Localization::new(Language::English);
+        assert!(
+            localization.missing_keys().is_empty(),
+            "missing translations: {:?}",
+            localization.missing_keys()
+        );
+        assert!(Language::ALL
+            .iter()
+            .all(|language| localization.strings[language].len() > 1));
+    }
+
+    #[test]
+    fn every_sheet_has_every_clip_inside_its_atlas() {
+        let library = AnimationLibrary::default();
+        for sheet in AnimationSheet::ALL {
+            for name in ClipName::ALL {
+                assert!(
+                    library.sheets[&sheet].contains_key(&name),
+                    "{sheet:?} has no {name:?} clip"
+                );
+                let clip = library.clip(sheet, name);
+                assert!(clip.first <= clip.last && clip.last < sheet.frames());
+            }
+        }
+    }
+
+    #[test]
+    fn shipped_eras_follow_the_original_periods() {
+        let eras = Eras::builtin();
+        assert_eq!(eras.all().len(), BUILTIN_ERAS.len());
+        let periods: Vec<usize> = (0..8).map(|level| eras.index_at(level)).collect();
+        assert_eq!(periods, [0, 0, 1, 1, 1, 2, 2, 2]);
+        let starts: Vec<u32> = (0..8).filter(|level| eras.starts_era(*level)).collect();
+        assert_eq!(starts, [0, 2, 5]);
+    }
+
+    #[test]
+    fn a_new_era_file_extends_the_run() {
+        let mut files: Vec<(String, String)> = BUILTIN_ERAS
+            .iter()
+            .map(|(name, content)| (name.to_string(), content.to_string()))
+            .collect();
+        // Starts on the first wave reaching 1800, the tenth one
+        files.push((
+            "3_middle_ages.ron".to_string(),
+            r#"(
+                name: "Middle Ages",
+                lore: "Knights and castles",
+                start: Date(1800),
+                chunk_tiles: ["images/map_chunk_2.png"],
+                music: "sounds/in_game_2.ogg",
+                natives: [(atlas: "images/mob_atlas_0.png", speed: 0.8)],
+                robots: [],
+                tint: (0.9, 0.8, 0.7),
+            )"#
+            .to_string(),
+        ));
+        // Broken definitions are left out
+        files.push(("4_broken.ron".to_string(), "(name: \"Broken\")".to_string()));
+        let eras = Eras::from_files(&files);
+        assert_eq!(eras.all().len(), 4);
+        assert_eq!(eras.index_at(8), 2);
+        assert_eq!(eras.index_at(9), 3);
+        assert!(eras.starts_era(9));
+        assert_eq!(eras.at(12).name, "Middle Ages");
+        // Without a single valid era the run goes back to the shipped ones
+        let eras = Eras::from_files(&files[files.len() - 1..]);
+        assert_eq!(eras.all().len(), BUILTIN_ERAS.len());
+    }
+
+    #[test]
+    fn story_beats_follow_the_run() {
+        let story = Story::default();
+        let eras = Eras::builtin();
+        let mut flaws = BTreeSet::new();
+        let mut seen = BTreeSet::new();
+        let beat_at = |level: u32, flaws: &BTreeSet<DebufChoices>, seen: &BTreeSet<String>| {
+            let moment = StoryMoment {
+                level,
+                era: eras.index_at(level),
+                era_start: eras.starts_era(level),
+                flaws,
+                deaths: 0,
+            };
+            story.next_beat(&moment, seen).map(|beat| beat.id.clone())
+        };
+        assert_eq!(beat_at(1, &flaws, &seen).as_deref(), Some("parent_choice"));
+        assert_eq!(beat_at(2, &flaws, &seen).as_deref(), Some("era_start"));
+        assert_eq!(beat_at(3, &flaws, &seen), None);
+        flaws.insert(DebufChoices::Speed);
+        assert_eq!(beat_at(3, &flaws, &seen).as_deref(), Some("slow_family"));
+        seen.insert("slow_family".to_string());
+        assert_eq!(beat_at(3, &flaws, &seen), None);
+    }
+
+    #[test]
+    fn daily_runs_share_a_seed_per_day_and_wave() {
+        assert_eq!(calendar_date(0), "1970-01-01");
+        assert_eq!(calendar_date(11_016), "2000-02-29");
+        assert_eq!(calendar_date(20_745), "2026-10-19");
+        let today = DailyRun {
+            day: 20_745,
+            scored: true,
+            menu_players: 2,
+            menu_difficulty: Difficulty::Hard,
+        };
+        let tomorrow = DailyRun {
+            day: 20_746,
+            scored: false,
+            menu_players: 2,
+            menu_difficulty: Difficulty::Hard,
+        };
+        let practice = DailyRun {
+            day: 20_745,
+            scored: false,
+            menu_players: 2,
+            menu_difficulty: Difficulty::Hard,
+        };
+        assert_eq!(today.wave_seed(3), practice.wave_seed(3));
+        let seeds = [
+            today.wave_seed(3),
+            today.parents_seed(3),
+            today.wave_seed(4),
+            tomorrow.wave_seed(3),
+        ];
+        assert!((1..seeds.len()).all(|i| !seeds[..i].contains(&seeds[i])));
+    }
+
+    #[test]
+    fn achievements_unlock_once_their_conditions_hold() {
+        let achievements = Achievements::default();
+        let mut unlocked = BTreeSet::new();
+        let mut flaws = BTreeSet::from([DebufChoices::Speed]);
+        let mut unlock = |score: &Score, bullets, flaws: &BTreeSet<DebufChoices>| {
+            let moment = AchievementMoment {
+                level: score.get_level_index(),
+                year: score.get_date(),
+                bullets,
+                flaws,
+                difficulty: score.difficulty(),
+            };
+            let ids: Vec<String> = achievements
+                .unlocked_by(&moment, &unlocked)
+                .into_iter()
+                .map(|index| achievements.list[index].id.clone())
+                .collect();
+            unlocked.extend(ids.iter().cloned());
+            ids
+        };
+        let score = Score::starting_at(1, Difficulty::Normal);
+        assert_eq!(unlock(&score, Some(3), &flaws), ["first_wave"]);
+        // Bullets only count on clearing a wave
+        assert!(unlock(&score, None, &flaws).is_empty());
+        assert_eq!(unlock(&score, Some(1), &flaws), ["single_shot"]);
+        flaws.extend([
+            DebufChoices::Bullets,
+            DebufChoices::BulletsTtl,
+            DebufChoices::Damage,
+            DebufChoices::BulletsSpeed,
+            DebufChoices::FireRate,
+        ]);
+        let score = Score::starting_at(6, Difficulty::Normal);
+        assert_eq!(
+            unlock(&score, None, &flaws),
+            ["year_2000", "year_1900", "full_heritage"]
+        );
+        let score = Score::starting_at(6, Difficulty::Nightmare);
+        assert_eq!(unlock(&score, None, &flaws), ["nightmare_1900"]);
+        // A run launched on a later wave is not played from the start
+        assert!(!score.full_run());
+        let mut score = Score::new(Difficulty::Normal);
+        score.level_up();
+        assert!(score.full_run());
+    }
+
+    #[test]
+    fn missing_strings_fall_back_to_english() {
+        let mut localization = Localization::new(Language::French);
+        localization
+            .strings
+            .get_mut(&Language::French)
+            .unwrap()
+            .remove("menu.start");
+        assert_eq!(localization.get("menu.start"), "Start");
+        assert_eq!(localization.get("settings.back"), "Retour");
+        assert_eq!(localization.get("not.a.key"), "not.a.key");
+        assert_eq!(
+            localization.format("retry.killed_in", &[("year", &1900)]),
+            "Votre ancêtre a été tué en l'an 1900"
+        );
+    }
+
+    fn assert_close(value: f32, expected: f32) {
+        assert!(
+            (value - expected).abs() < 1e-4,
+            "expected {expected}, got {value}"
+        );
+    }
+
+    #[test]
+    fn endless_curve_expected_values() {
+        let curve = EndlessCurve::default();
+        // (level, amount, rate, quantity_per_spawn, robot_ratio, elite_ratio)
+        let expected = [
+            (10, 1840, 0.442_307_7, 4, 0.266_391_9, 0.004_938_0),
+            (20, 4240, 0.319_444_4, 6, 0.403_479_7, 0.048_085_6),
+            (50, 11440, 0.174_242_4, 14, 0.622_522_3, 0.128_240_7),
+            (100, 23440, 0.1, 26, 0.725_922_6, 0.179_440_6),
+        ];
+        for (level, amount, rate, quantity, robot_ratio, elite_ratio) in expected {
+            let wave = curve.wave_for_level_id(level);
+            assert_eq!(wave.amount, amount, "amount at level {level}");
+            assert_close(wave.rate, rate);
+            assert_eq!(wave.quantity_per_spawn, quantity, "burst at level {level}");
+            assert_close(wave.robot_ratio, robot_ratio);
+            assert_close(wave.elite_ratio, elite_ratio);
+        }
+    }
+
+    #[test]
+    fn endless_curve_keeps_increasing_pressure() {
+        let curve = EndlessCurve::default();
+        let mut previous = curve.wave_for_level_id(MAX_WAVE_CALIBRATION);
+        for level in 10..=100 {
+            let wave = curve.wave_for_level_id(level);
+            assert!(wave.amount > previous.amount, "amount at level {level}");
+            assert!(wave.rate <= previous.rate, "rate at level {level}");
+            assert!(wave.rate >= curve.min_spawn_delay);
+            assert!(wave.quantity_per_spawn >= previous.quantity_per_spawn);
+            // Ratios never jump by more than a couple percent between two levels
+            assert!(wave.robot_ratio >= previous.robot_ratio);
+            assert!(wave.robot_ratio - previous.robot_ratio < 0.02);
+            assert!(wave.elite_ratio >= previous.elite_ratio);
+            assert!(wave.elite_ratio - previous.elite_ratio < 0.02);
+            assert!(wave.robot_ratio <= curve.max_robot_ratio);
+            assert!(wave.elite_ratio <= curve.max_elite_ratio);
+            previous = wave;
+        }
+    }
+
+    #[test]
+    fn difficulty_scales_endless_waves() {
+        let curve = EndlessCurve::default();
+        let mut normal = TotalToSpawn::default();
+        let mut nightmare = TotalToSpawn::default();
+        normal.update_paramter_for_level_id(30, Difficulty::Normal, &curve);
+        nightmare.update_paramter_for_level_id(30, Difficulty::Nightmare, &curve);
+        assert!(nightmare.amount > normal.amount);
+        assert!(nightmare.rate < normal.rate);
+        assert!(nightmare.quantity_per_spawn > normal.quantity_per_spawn);
+    }
 }
@@ -0,0 +1,36 @@
+use std::{fs, path::PathBuf};
+
+use bevy::log::warn;
+use serde::{de::DeserializeOwned, Serialize};
+
+// Every file the game persists lives in this folder, next to the executable working directory
+const SAVE_FOLDER: &str = "saves";
+
+fn save_path(file_name: &str) -> PathBuf {
+    PathBuf::from(SAVE_FOLDER).join(file_name)
+}
+
+// Missing or unreadable files fall back to the default value, a broken save must never block the game
+pub fn load_or_default<T: DeserializeOwned + Default>(file_name: &str) -> T {
+    let path = save_path(file_name);
+    match fs::read_to_string(&path) {
+        Ok(content) => ron::from_str(&content).unwrap_or_else(|error| {
+            warn!("Ignoring corrupted save {}: {error}", path.display());
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+pub fn save<T: Serialize>(file_name: &str, value: &T) {
+    let path = save_path(file_name);
+    let result = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|error| error.to_string())
+        .and_then(|content| {
+            fs::create_dir_all(SAVE_FOLDER).map_err(|error| error.to_string())?;
+            fs::write(&path, content).map_err(|error| error.to_string())
+        });
+    if let Err(error) = result {
+        warn!("Could not write save {}: {error}", path.display());
+    }
+}
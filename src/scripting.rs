@@ -0,0 +1,662 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::SystemTime,
+};
+
+use bevy::{
+    log::{info, warn},
+    prelude::{Resource, Vec2},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rhai::{
+    module_resolvers::DummyModuleResolver, CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs,
+    Map, Scope, AST,
+};
+use serde::Deserialize;
+
+use crate::{
+    components::DebufChoices,
+    constants::{ASSETS_FOLDER, SCRIPT_HOOKS, SCRIPT_MAX_OPERATIONS, SCRIPT_MAX_SPAWNS},
+    resource::base_folder,
+    StatsRes,
+};
+
+// Player stats scripts can read and change, by the name they use
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stat {
+    Speed,
+    Damage,
+    Health,
+    FireRate,
+    Bullets,
+    BulletsTtl,
+    BulletsSpeed,
+    Decay,
+}
+
+impl Stat {
+    pub const ALL: [Stat; 8] = [
+        Stat::Speed,
+        Stat::Damage,
+        Stat::Health,
+        Stat::FireRate,
+        Stat::Bullets,
+        Stat::BulletsTtl,
+        Stat::BulletsSpeed,
+        Stat::Decay,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stat::Speed => "speed",
+            Stat::Damage => "damage",
+            Stat::Health => "health",
+            Stat::FireRate => "fire_rate",
+            Stat::Bullets => "bullets",
+            Stat::BulletsTtl => "bullets_ttl",
+            Stat::BulletsSpeed => "bullets_speed",
+            Stat::Decay => "decay",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|stat| stat.name() == name)
+    }
+
+    pub fn get(&self, stats: &StatsRes) -> f32 {
+        match self {
+            Stat::Speed => stats.player_speed,
+            Stat::Damage => stats.player_damage,
+            Stat::Health => stats.player_health,
+            Stat::FireRate => stats.player_fire_rate,
+            Stat::Bullets => stats.player_bullets as f32,
+            Stat::BulletsTtl => stats.player_bullets_ttl as f32,
+            Stat::BulletsSpeed => stats.player_bullets_speed,
+            Stat::Decay => stats.player_decay,
+        }
+    }
+
+    // Values a script may set, anything past them would stall or break the game
+    pub fn range(&self) -> RangeInclusive<f32> {
+        match self {
+            Stat::Speed => 1.0..=5000.0,
+            Stat::Damage => 0.0..=1000.0,
+            Stat::Health => 0.01..=1000.0,
+            Stat::FireRate => 0.01..=10.0,
+            Stat::Bullets => 1.0..=64.0,
+            Stat::BulletsTtl => 1.0..=100.0,
+            Stat::BulletsSpeed => 1.0..=10000.0,
+            Stat::Decay => 0.0..=1.0,
+        }
+    }
+
+    // Counts keep at least one bullet and one hit
+    pub fn set(&self, stats: &mut StatsRes, value: f32) {
+        match self {
+            Stat::Speed => stats.player_speed = value,
+            Stat::Damage => stats.player_damage = value,
+            Stat::Health => stats.player_health = value,
+            Stat::FireRate => stats.player_fire_rate = value,
+            Stat::Bullets => stats.player_bullets = value.round().max(1.0) as u32,
+            Stat::BulletsTtl => stats.player_bullets_ttl = value.round().max(1.0) as i32,
+            Stat::BulletsSpeed => stats.player_bullets_speed = value,
+            Stat::Decay => stats.player_decay = value,
+        }
+    }
+}
+
+// Runs a script on a range of waves, by level index
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WaveHook {
+    pub script: String,
+    #[serde(default)]
+    pub first_level: u32,
+    #[serde(default)]
+    pub last_level: Option<u32>,
+}
+
+// Runs a script once anyone in the party inherited the flaw
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TraitHook {
+    pub flaw: DebufChoices,
+    pub script: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScriptHooks {
+    pub waves: Vec<WaveHook>,
+    pub traits: Vec<TraitHook>,
+}
+
+impl ScriptHooks {
+    // The shipped hooks name their scripts from the assets folder, they end up as file paths
+    pub fn load() -> Self {
+        let assets = base_folder().join(ASSETS_FOLDER);
+        let path = assets.join(SCRIPT_HOOKS);
+        let mut hooks: Self = match fs::read_to_string(&path) {
+            Ok(content) => ron::from_str(&content).unwrap_or_else(|error| {
+                warn!("Ignoring script hooks {}: {error}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+        for script in hooks.scripts_mut() {
+            *script = assets.join(&*script).display().to_string();
+        }
+        hooks
+    }
+
+    pub fn scripts_mut(&mut self) -> impl Iterator<Item = &mut String> {
+        self.waves
+            .iter_mut()
+            .map(|hook| &mut hook.script)
+            .chain(self.traits.iter_mut().map(|hook| &mut hook.script))
+    }
+
+    pub fn extend(&mut self, other: ScriptHooks) {
+        self.waves.extend(other.waves);
+        self.traits.extend(other.traits);
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct PlayerView {
+    pub position: Vec2,
+    pub alive: bool,
+    // Indexed by `Stat`
+    pub stats: [f32; 8],
+}
+
+impl PlayerView {
+    pub fn new(position: Vec2, alive: bool, stats: &StatsRes) -> Self {
+        Self {
+            position,
+            alive,
+            stats: Stat::ALL.map(|stat| stat.get(stats)),
+        }
+    }
+}
+
+// The game as scripts see it, refreshed before every call
+#[derive(Clone, Default)]
+pub struct ScriptView {
+    // As shown to the player, from 1
+    pub wave: u32,
+    pub year: i32,
+    pub players: Vec<PlayerView>,
+}
+
+// What scripts ask the game to do, applied once the handlers returned
+#[derive(Debug, PartialEq)]
+pub enum ScriptCommand {
+    Spawn {
+        position: Vec2,
+        robot: bool,
+        elite: bool,
+    },
+    SetStat {
+        player: usize,
+        stat: Stat,
+        value: f32,
+    },
+}
+
+// Everything the functions given to scripts can touch
+struct Host {
+    view: ScriptView,
+    commands: Vec<ScriptCommand>,
+    spawns: usize,
+    rng: StdRng,
+}
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+impl Host {
+    fn player(&self, index: i64) -> ScriptResult<&PlayerView> {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| self.view.players.get(index))
+            .ok_or_else(|| format!("there is no player {index}").into())
+    }
+
+    fn spawn(&mut self, x: f64, y: f64, robot: bool, elite: bool) {
+        // A runaway script floods nothing past the cap
+        if self.spawns < SCRIPT_MAX_SPAWNS {
+            self.spawns += 1;
+            self.commands.push(ScriptCommand::Spawn {
+                position: Vec2::new(x as f32, y as f32),
+                robot,
+                elite,
+            });
+        }
+    }
+
+    fn set_stat(&mut self, player: i64, name: &str, value: f64) -> ScriptResult<()> {
+        let stat = Stat::from_name(name).ok_or_else(|| format!("there is no stat {name}"))?;
+        self.player(player)?;
+        let value = value as f32;
+        let range = stat.range();
+        // NaN is in no range
+        if !range.contains(&value) {
+            return Err(format!(
+                "{name} must be between {} and {}, not {value}",
+                range.start(),
+                range.end()
+            )
+            .into());
+        }
+        let player = player as usize;
+        self.view.players[player].stats[stat as usize] = value;
+        self.commands.push(ScriptCommand::SetStat {
+            player,
+            stat,
+            value,
+        });
+        Ok(())
+    }
+}
+
+fn lock(host: &Mutex<Host>) -> MutexGuard<'_, Host> {
+    host.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// No file, module or eval access and a cap on the work done by a call, scripts only reach the
+// game through the functions below
+fn sandboxed_engine(host: &Arc<Mutex<Host>>) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(SCRIPT_MAX_OPERATIONS)
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(4096)
+        .set_max_array_size(1024)
+        .set_max_map_size(256)
+        .set_module_resolver(DummyModuleResolver::new())
+        .disable_symbol("eval")
+        .on_print(|text| info!("Script: {text}"));
+
+    let shared = host.clone();
+    engine.register_fn("spawn_enemy", move |x: f64, y: f64, robot: bool| {
+        lock(&shared).spawn(x, y, robot, false)
+    });
+    let shared = host.clone();
+    engine.register_fn("spawn_elite", move |x: f64, y: f64, robot: bool| {
+        lock(&shared).spawn(x, y, robot, true)
+    });
+    let shared = host.clone();
+    engine.register_fn("player_count", move || {
+        lock(&shared).view.players.len() as i64
+    });
+    let shared = host.clone();
+    engine.register_fn("player_x", move |player: i64| -> ScriptResult<f64> {
+        Ok(lock(&shared).player(player)?.position.x as f64)
+    });
+    let shared = host.clone();
+    engine.register_fn("player_y", move |player: i64| -> ScriptResult<f64> {
+        Ok(lock(&shared).player(player)?.position.y as f64)
+    });
+    let shared = host.clone();
+    engine.register_fn("player_alive", move |player: i64| -> ScriptResult<bool> {
+        Ok(lock(&shared).player(player)?.alive)
+    });
+    let shared = host.clone();
+    engine.register_fn(
+        "stat",
+        move |player: i64, name: &str| -> ScriptResult<f64> {
+            let stat = Stat::from_name(name).ok_or_else(|| format!("there is no stat {name}"))?;
+            Ok(lock(&shared).player(player)?.stats[stat as usize] as f64)
+        },
+    );
+    let shared = host.clone();
+    engine.register_fn("set_stat", move |player: i64, name: &str, value: f64| {
+        lock(&shared).set_stat(player, name, value)
+    });
+    let shared = host.clone();
+    engine.register_fn("wave", move || lock(&shared).view.wave as i64);
+    let shared = host.clone();
+    engine.register_fn("year", move || lock(&shared).view.year as i64);
+    let shared = host.clone();
+    engine.register_fn("random", move || lock(&shared).rng.gen::<f64>());
+    engine
+}
+
+struct Script {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    // None until the file compiles
+    ast: Option<AST>,
+    // Bound to `this`, kept from a call to the next
+    state: Dynamic,
+    // A script that failed sits out until its file changes
+    failed: bool,
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+// Scripts attached to waves and flaws, compiled once and again whenever their file changes
+#[derive(Resource)]
+pub struct Scripts {
+    engine: Engine,
+    host: Arc<Mutex<Host>>,
+    scripts: Vec<Script>,
+    waves: Vec<(WaveHook, usize)>,
+    traits: Vec<(DebufChoices, usize)>,
+}
+
+impl Scripts {
+    pub fn new(hooks: &ScriptHooks) -> Self {
+        let host = Arc::new(Mutex::new(Host {
+            view: ScriptView::default(),
+            commands: vec![],
+            spawns: 0,
+            rng: StdRng::seed_from_u64(0),
+        }));
+        let mut scripts = Self {
+            engine: sandboxed_engine(&host),
+            host,
+            scripts: vec![],
+            waves: vec![],
+            traits: vec![],
+        };
+        for hook in &hooks.waves {
+            let index = scripts.index(&hook.script);
+            scripts.waves.push((hook.clone(), index));
+        }
+        for hook in &hooks.traits {
+            let index = scripts.index(&hook.script);
+            scripts.traits.push((hook.flaw, index));
+        }
+        scripts
+    }
+
+    // Hooks sharing a file share the script and its state
+    fn index(&mut self, path: &str) -> usize {
+        let path = PathBuf::from(path);
+        if let Some(index) = self.scripts.iter().position(|script| script.path == path) {
+            return index;
+        }
+        let mut script = Script {
+            modified: modified(&path),
+            path,
+            ast: None,
+            state: Dynamic::from_map(Map::new()),
+            failed: false,
+        };
+        script.ast = self.compile(&script.path);
+        self.scripts.push(script);
+        self.scripts.len() - 1
+    }
+
+    fn compile(&self, path: &Path) -> Option<AST> {
+        let compiled = fs::read_to_string(path)
+            .map_err(|error| error.to_string())
+            .and_then(|source| {
+                self.engine
+                    .compile(source)
+                    .map_err(|error| error.to_string())
+            });
+        compiled
+            .map_err(|error| warn!("Could not compile script {}: {error}", path.display()))
+            .ok()
+    }
+
+    // Scripts of the wave and of the flaws the party carries
+    pub fn active(&self, level: u32, flaws: &BTreeSet<DebufChoices>) -> Vec<usize> {
+        let waves = self.waves.iter().filter(|(hook, _)| {
+            level >= hook.first_level && hook.last_level.is_none_or(|last| level <= last)
+        });
+        let traits = self.traits.iter().filter(|(flaw, _)| flaws.contains(flaw));
+        let mut active: Vec<usize> = waves
+            .map(|(_, index)| *index)
+            .chain(traits.map(|(_, index)| *index))
+            .collect();
+        active.sort();
+        active.dedup();
+        active
+    }
+
+    pub fn for_flaws(&self, flaws: &[DebufChoices]) -> Vec<usize> {
+        let mut active: Vec<usize> = self
+            .traits
+            .iter()
+            .filter(|(flaw, _)| flaws.contains(flaw))
+            .map(|(_, index)| *index)
+            .collect();
+        active.sort();
+        active.dedup();
+        active
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        lock(&self.host).rng = StdRng::seed_from_u64(seed);
+    }
+
+    // Run the handler of every given script that has one, in order, and collect what they asked for
+    pub fn call(
+        &mut self,
+        active: &[usize],
+        view: ScriptView,
+        handler: &str,
+        args: impl FuncArgs + Clone,
+    ) -> Vec<ScriptCommand> {
+        {
+            let mut host = lock(&self.host);
+            host.view = view;
+            host.commands.clear();
+            host.spawns = 0;
+        }
+        for &index in active {
+            let script = &mut self.scripts[index];
+            let Some(ast) = &script.ast else {
+                continue;
+            };
+            if script.failed
+                || !ast
+                    .iter_functions()
+                    .any(|function| function.name == handler)
+            {
+                continue;
+            }
+            let options = CallFnOptions::new()
+                .eval_ast(false)
+                .bind_this_ptr(&mut script.state);
+            let result = self.engine.call_fn_with_options::<Dynamic>(
+                options,
+                &mut Scope::new(),
+                ast,
+                handler,
+                args.clone(),
+            );
+            if let Err(error) = result {
+                warn!(
+                    "Script {} stopped in {handler}: {error}",
+                    script.path.display()
+                );
+                script.failed = true;
+            }
+        }
+        std::mem::take(&mut lock(&self.host).commands)
+    }
+
+    // A script that no longer compiles keeps running its last version
+    pub fn reload_changed(&mut self) {
+        for index in 0..self.scripts.len() {
+            let time = modified(&self.scripts[index].path);
+            if time == self.scripts[index].modified {
+                continue;
+            }
+            self.scripts[index].modified = time;
+            if let Some(ast) = self.compile(&self.scripts[index].path) {
+                let script = &mut self.scripts[index];
+                info!("Reloaded script {}", script.path.display());
+                script.ast = Some(ast);
+                script.failed = false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scripts_with(name: &str, source: &str) -> (Scripts, PathBuf) {
+        let path = std::env::temp_dir().join(format!("{name}_{}.rhai", std::process::id()));
+        fs::write(&path, source).unwrap();
+        let hooks = ScriptHooks {
+            waves: vec![WaveHook {
+                script: path.display().to_string(),
+                first_level: 2,
+                last_level: Some(4),
+            }],
+            traits: vec![TraitHook {
+                flaw: DebufChoices::Damage,
+                script: path.display().to_string(),
+            }],
+        };
+        (Scripts::new(&hooks), path)
+    }
+
+    fn view() -> ScriptView {
+        ScriptView {
+            wave: 3,
+            year: 2030,
+            players: vec![PlayerView::new(
+                Vec2::new(10.0, 20.0),
+                true,
+                &StatsRes::default(),
+            )],
+        }
+    }
+
+    #[test]
+    fn handlers_spawn_change_stats_and_keep_their_state() {
+        let (mut scripts, path) = scripts_with(
+            "handlers",
+            r#"
+            fn on_wave_start(wave) { this.kills = 0; }
+            fn on_kill(x, y, elite) {
+                this.kills += 1;
+                if this.kills == 2 {
+                    spawn_enemy(x + player_x(0), y, true);
+                    set_stat(0, "speed", stat(0, "speed") * 2.0);
+                }
+            }
+            "#,
+        );
+        assert_eq!(scripts.active(0, &BTreeSet::new()), Vec::<usize>::new());
+        let active = scripts.active(3, &BTreeSet::new());
+        assert_eq!(
+            active,
+            scripts.active(9, &BTreeSet::from([DebufChoices::Damage]))
+        );
+        assert_eq!(active, scripts.for_flaws(&[DebufChoices::Damage]));
+
+        scripts.call(&active, view(), "on_wave_start", (3_i64,));
+        let first = scripts.call(&active, view(), "on_kill", (1.0_f64, 2.0_f64, false));
+        let second = scripts.call(&active, view(), "on_kill", (1.0_f64, 2.0_f64, false));
+        fs::remove_file(path).unwrap();
+        assert!(first.is_empty());
+        assert_eq!(
+            second,
+            [
+                ScriptCommand::Spawn {
+                    position: Vec2::new(11.0, 2.0),
+                    robot: true,
+                    elite: false,
+                },
+                ScriptCommand::SetStat {
+                    player: 0,
+                    stat: Stat::Speed,
+                    value: StatsRes::default().player_speed * 2.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn runaway_scripts_are_stopped() {
+        let (mut scripts, path) = scripts_with(
+            "runaway",
+            r#"
+            fn on_tick(dt) { loop { spawn_enemy(0.0, 0.0, false); } }
+            "#,
+        );
+        let active = scripts.active(2, &BTreeSet::new());
+        let commands = scripts.call(&active, view(), "on_tick", (0.1_f64,));
+        assert_eq!(commands.len(), SCRIPT_MAX_SPAWNS);
+        // Failed once, the script waits for a fix
+        let commands = scripts.call(&active, view(), "on_tick", (0.1_f64,));
+        assert!(commands.is_empty());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn out_of_range_stats_are_rejected() {
+        let set = |name: &str, source: &str| {
+            let (mut scripts, path) = scripts_with(name, source);
+            let active = scripts.active(2, &BTreeSet::new());
+            let commands = scripts.call(&active, view(), "on_tick", (0.1_f64,));
+            fs::remove_file(path).unwrap();
+            commands
+        };
+        let nan = set(
+            "nan_stat",
+            r#"fn on_tick(dt) { set_stat(0, "speed", 0.0 / 0.0); }"#,
+        );
+        assert!(nan.is_empty());
+        let huge = set(
+            "huge_stat",
+            r#"fn on_tick(dt) { set_stat(0, "bullets", 1e9); }"#,
+        );
+        assert!(huge.is_empty());
+        let capped = set(
+            "capped_stat",
+            r#"fn on_tick(dt) { set_stat(0, "bullets", 64.0); }"#,
+        );
+        assert_eq!(
+            capped,
+            [ScriptCommand::SetStat {
+                player: 0,
+                stat: Stat::Bullets,
+                value: 64.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn shipped_scripts_run() {
+        let path = Path::new(ASSETS_FOLDER).join(SCRIPT_HOOKS);
+        ron::from_str::<ScriptHooks>(&fs::read_to_string(path).unwrap()).unwrap();
+        let hooks = ScriptHooks {
+            waves: vec![WaveHook {
+                script: "assets/scripts/robot_ring.rhai".to_string(),
+                first_level: 0,
+                last_level: None,
+            }],
+            traits: vec![TraitHook {
+                flaw: DebufChoices::BulletsTtl,
+                script: "assets/scripts/split_on_kill.rhai".to_string(),
+            }],
+        };
+        let mut scripts = Scripts::new(&hooks);
+        let active = scripts.active(2, &BTreeSet::from([DebufChoices::BulletsTtl]));
+        assert_eq!(active.len(), 2);
+        // A ring of 6 robots plus one a wave
+        let ring = scripts.call(&active, view(), "on_tick", (31.0_f64,));
+        assert_eq!(ring.len(), 9);
+        let inherit = scripts.call(&active, view(), "on_inherit", (0_i64,));
+        assert_eq!(inherit.len(), 1);
+    }
+}
@@ -0,0 +1,313 @@
+use std::{collections::BTreeMap, fmt::Display, time::Duration};
+
+use bevy::{time::TimeUpdateStrategy, utils::Instant};
+
+use crate::{
+    build_app,
+    components::DebufChoices,
+    constants::SIMULATION_STEP,
+    launch::{parse_difficulty, parse_value, LaunchConfig, LaunchError},
+    resource::{Bot, BotLog, BotMovement, BotParents, BotRunRecord, Persistence},
+    Difficulty,
+};
+
+pub const BALANCE_USAGE: &str = "Usage: balance [OPTIONS]
+
+Play games with a bot and report how far it gets.
+
+Options:
+  --games <NUMBER>         Games to play (default 20)
+  --bot <kite|stand>       Run in circles around the start or stand still, always shooting (default kite)
+  --parents <FLAW>         'random', or the flaw to never inherit: speed, bullets, piercing,
+                           damage, bullet-speed or fire-rate (default random)
+  --max-level <NUMBER>     Stop a game once this wave is reached (default 50)
+  --seed <NUMBER>          Seed of the whole batch, random when not given
+  --difficulty <NAME>      story, normal, hard or nightmare
+  --help                   Print this message";
+
+// How many of the most lethal flaw combinations are listed
+const REPORTED_COMBINATIONS: usize = 10;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BalanceConfig {
+    pub games: usize,
+    pub bot: Bot,
+    pub seed: Option<u64>,
+    pub difficulty: Difficulty,
+}
+
+impl Default for BalanceConfig {
+    fn default() -> Self {
+        Self {
+            games: 20,
+            bot: Bot {
+                movement: BotMovement::KiteCircles,
+                parents: BotParents::Random,
+                max_level: Some(50),
+            },
+            seed: None,
+            difficulty: Difficulty::default(),
+        }
+    }
+}
+
+fn parse_movement(flag: &str, value: &str) -> Result<BotMovement, LaunchError> {
+    match value {
+        "kite" => Ok(BotMovement::KiteCircles),
+        "stand" => Ok(BotMovement::StandStill),
+        _ => Err(LaunchError::InvalidValue {
+            flag: flag.to_string(),
+            value: value.to_string(),
+            expected: "'kite' or 'stand'",
+        }),
+    }
+}
+
+fn parse_parents(flag: &str, value: &str) -> Result<BotParents, LaunchError> {
+    let flaw = match value {
+        "random" => return Ok(BotParents::Random),
+        "speed" => DebufChoices::Speed,
+        "bullets" => DebufChoices::Bullets,
+        "piercing" => DebufChoices::BulletsTtl,
+        "damage" => DebufChoices::Damage,
+        "bullet-speed" => DebufChoices::BulletsSpeed,
+        "fire-rate" => DebufChoices::FireRate,
+        _ => {
+            return Err(LaunchError::InvalidValue {
+                flag: flag.to_string(),
+                value: value.to_string(),
+                expected: "'random' or a flaw name",
+            })
+        }
+    };
+    Ok(BotParents::Avoid(flaw))
+}
+
+impl BalanceConfig {
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, LaunchError> {
+        let mut config = Self::default();
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| LaunchError::MissingValue(flag.clone()))
+            };
+            match flag.as_str() {
+                "--help" | "-h" => return Err(LaunchError::HelpRequested),
+                "--games" => config.games = parse_value(&flag, &value()?, "a positive number")?,
+                "--bot" => config.bot.movement = parse_movement(&flag, &value()?)?,
+                "--parents" => config.bot.parents = parse_parents(&flag, &value()?)?,
+                "--max-level" => {
+                    config.bot.max_level = Some(parse_value(&flag, &value()?, "a positive number")?)
+                }
+                "--seed" => config.seed = Some(parse_value(&flag, &value()?, "a positive number")?),
+                "--difficulty" => config.difficulty = parse_difficulty(&flag, &value()?)?,
+                _ => return Err(LaunchError::UnknownFlag(flag)),
+            }
+        }
+        Ok(config)
+    }
+}
+
+// Play the games through the real game systems, one fixed time step per update
+pub fn run_balance(config: &BalanceConfig) -> Vec<BotRunRecord> {
+    let mut app = build_app(&LaunchConfig {
+        seed: config.seed,
+        difficulty: config.difficulty,
+        headless: true,
+        editor: false,
+        ..Default::default()
+    });
+    app.insert_resource(Persistence { enabled: false })
+        .insert_resource(config.bot);
+    app.setup();
+    // A manual duration is added to the wall clock, so the instant is moved forward by hand
+    let step = Duration::from_secs_f32(SIMULATION_STEP);
+    let mut now = Instant::now();
+    while app.world.resource::<BotLog>().runs.len() < config.games {
+        now += step;
+        app.insert_resource(TimeUpdateStrategy::ManualInstant(now));
+        app.update();
+    }
+    app.world.resource_mut::<BotLog>().runs.drain(..).collect()
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CombinationStats {
+    pub flaws: [DebufChoices; 3],
+    pub picks: usize,
+    // Games lost in the wave right after inheriting these flaws
+    pub deaths: usize,
+}
+
+impl CombinationStats {
+    pub fn lethality(&self) -> f32 {
+        self.deaths as f32 / self.picks as f32
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BalanceReport {
+    pub games: usize,
+    pub survived: usize,
+    // Games ended on every wave, with the date of that wave
+    pub levels: BTreeMap<u32, (i32, usize)>,
+    // Mean seconds to clear every wave and how many games cleared it
+    pub wave_seconds: Vec<(f32, usize)>,
+    // Most lethal first
+    pub combinations: Vec<CombinationStats>,
+}
+
+impl BalanceReport {
+    pub fn new(runs: &[BotRunRecord]) -> Self {
+        let mut levels: BTreeMap<u32, (i32, usize)> = BTreeMap::new();
+        let mut wave_seconds: Vec<(f32, usize)> = vec![];
+        let mut combinations: BTreeMap<[DebufChoices; 3], CombinationStats> = BTreeMap::new();
+        for run in runs {
+            levels.entry(run.level).or_insert((run.date, 0)).1 += 1;
+            for (i, seconds) in run.wave_seconds.iter().enumerate() {
+                if wave_seconds.len() <= i {
+                    wave_seconds.push((0.0, 0));
+                }
+                wave_seconds[i].0 += seconds;
+                wave_seconds[i].1 += 1;
+            }
+            for (i, flaws) in run.debuffs.iter().enumerate() {
+                let mut flaws = *flaws;
+                flaws.sort();
+                let stats = combinations.entry(flaws).or_insert(CombinationStats {
+                    flaws,
+                    picks: 0,
+                    deaths: 0,
+                });
+                stats.picks += 1;
+                if !run.survived && i + 1 == run.debuffs.len() {
+                    stats.deaths += 1;
+                }
+            }
+        }
+        wave_seconds
+            .iter_mut()
+            .for_each(|(seconds, games)| *seconds /= *games as f32);
+        let mut combinations: Vec<CombinationStats> = combinations.into_values().collect();
+        combinations.sort_by(|a, b| {
+            b.lethality()
+                .total_cmp(&a.lethality())
+                .then(b.picks.cmp(&a.picks))
+        });
+        Self {
+            games: runs.len(),
+            survived: runs.iter().filter(|run| run.survived).count(),
+            levels,
+            wave_seconds,
+            combinations,
+        }
+    }
+}
+
+impl Display for BalanceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} games, {} reached the level cap",
+            self.games, self.survived
+        )?;
+        writeln!(f, "\nGames ended on:")?;
+        for (level, (date, games)) in &self.levels {
+            writeln!(
+                f,
+                "  wave {:>3} (year {date}) {games:>5} {}",
+                level + 1,
+                "#".repeat(games * 40 / self.games.max(1))
+            )?;
+        }
+        writeln!(f, "\nMean time to clear:")?;
+        for (i, (seconds, games)) in self.wave_seconds.iter().enumerate() {
+            writeln!(f, "  wave {:>3} {seconds:>7.1}s ({games} games)", i + 1)?;
+        }
+        writeln!(
+            f,
+            "\nMost lethal flaws, deaths in the wave after inheriting them:"
+        )?;
+        for stats in self.combinations.iter().take(REPORTED_COMBINATIONS) {
+            let flaws = stats
+                .flaws
+                .iter()
+                .map(|flaw| format!("{flaw:?}"))
+                .collect::<Vec<String>>()
+                .join(", ");
+            writeln!(
+                f,
+                "  {:>3.0}% ({}/{}) {flaws}",
+                stats.lethality() * 100.0,
+                stats.deaths,
+                stats.picks
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_aggregates_runs() {
+        let runs = vec![
+            BotRunRecord {
+                level: 2,
+                date: 2030,
+                survived: false,
+                wave_seconds: vec![10.0, 20.0],
+                debuffs: vec![
+                    [
+                        DebufChoices::Speed,
+                        DebufChoices::Damage,
+                        DebufChoices::Bullets,
+                    ],
+                    [
+                        DebufChoices::FireRate,
+                        DebufChoices::BulletsTtl,
+                        DebufChoices::BulletsSpeed,
+                    ],
+                ],
+            },
+            BotRunRecord {
+                level: 1,
+                date: 2065,
+                survived: false,
+                wave_seconds: vec![30.0],
+                debuffs: vec![[
+                    DebufChoices::Bullets,
+                    DebufChoices::Speed,
+                    DebufChoices::Damage,
+                ]],
+            },
+        ];
+        let report = BalanceReport::new(&runs);
+        assert_eq!(report.games, 2);
+        assert_eq!(report.levels[&1], (2065, 1));
+        assert_eq!(report.levels[&2], (2030, 1));
+        assert_eq!(report.wave_seconds, vec![(20.0, 2), (20.0, 1)]);
+        // The same flaws in a different order are one combination
+        assert_eq!(report.combinations.len(), 2);
+        assert_eq!(report.combinations[0].picks, 1);
+        assert_eq!(report.combinations[0].deaths, 1);
+        assert_eq!(report.combinations[1].picks, 2);
+        assert_eq!(report.combinations[1].deaths, 1);
+    }
+
+    #[test]
+    fn parses_bot_options() {
+        let config = BalanceConfig::from_args(
+            ["--games", "5", "--bot", "stand", "--parents", "damage"]
+                .iter()
+                .map(|arg| arg.to_string()),
+        )
+        .unwrap();
+        assert_eq!(config.games, 5);
+        assert_eq!(config.bot.movement, BotMovement::StandStill);
+        assert_eq!(config.bot.parents, BotParents::Avoid(DebufChoices::Damage));
+    }
+}
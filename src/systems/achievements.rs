@@ -0,0 +1,187 @@
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::{
+        AssetServer, BuildChildren, Color, Commands, DespawnRecursiveExt, Entity, EventReader,
+        EventWriter, NodeBundle, Query, Res, ResMut, With,
+    },
+    time::{Time, Timer, TimerMode},
+    ui::{
+        AlignItems, FlexDirection, JustifyContent, PositionType, Size, Style, UiRect, Val, ZIndex,
+    },
+    utils::default,
+};
+
+use crate::{
+    components::{Toast, ToastTimer},
+    constants::{ACHIEVEMENT_TOAST_SECONDS, UI_PANEL_COLOR, UI_TEXT_COLOR},
+    resource::{
+        AchievementMoment, AchievementProgress, Achievements, Localization, Party, Persistence,
+        Score,
+    },
+    systems::ui::{label, panel},
+};
+
+const TITLE_COLOR: Color = Color::rgb(0.9, 0.8, 0.3);
+const TOAST_HEIGHT: f32 = 90.0;
+
+fn toast_top(slot: usize) -> Val {
+    Val::Px(20.0 + slot as f32 * TOAST_HEIGHT)
+}
+
+pub struct AchievementUnlockedEvent {
+    // Index in the achievements list
+    pub index: usize,
+}
+
+#[derive(SystemParam)]
+pub struct AchievementContext<'w> {
+    achievements: Res<'w, Achievements>,
+    progress: ResMut<'w, AchievementProgress>,
+    score: Res<'w, Score>,
+    party: Res<'w, Party>,
+    persistence: Res<'w, Persistence>,
+    unlocked_event: EventWriter<'w, AchievementUnlockedEvent>,
+}
+
+impl AchievementContext<'_> {
+    fn check(&mut self, bullets: Option<u32>) {
+        // Bot and demo runs unlock nothing, nor do runs launched past the first wave
+        if !self.persistence.enabled || !self.score.full_run() {
+            return;
+        }
+        let moment = AchievementMoment {
+            level: self.score.get_level_index(),
+            year: self.score.get_date(),
+            bullets,
+            flaws: &self.party.flaws,
+            difficulty: self.score.difficulty(),
+        };
+        let unlocked = self
+            .achievements
+            .unlocked_by(&moment, &self.progress.unlocked);
+        if unlocked.is_empty() {
+            return;
+        }
+        for index in unlocked {
+            let id = self.achievements.list[index].id.clone();
+            self.progress.unlocked.insert(id);
+            self.unlocked_event.send(AchievementUnlockedEvent { index });
+        }
+        self.progress.save();
+    }
+}
+
+// The level menu opens on a cleared wave, with the stats it was played with
+pub fn achievements_wave_cleared(mut context: AchievementContext) {
+    // Co-op players after the first one open it again on the same wave
+    if context.party.picking > 0 {
+        return;
+    }
+    let bullets = context
+        .party
+        .players
+        .iter()
+        .map(|stats| stats.player_bullets)
+        .max();
+    context.check(bullets);
+}
+
+// Every parent is picked, the flaws they passed on count
+pub fn achievements_wave_start(mut context: AchievementContext) {
+    context.check(None);
+}
+
+pub fn spawn_achievement_toasts(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    achievements: Res<Achievements>,
+    localization: Res<Localization>,
+    mut unlocked_event: EventReader<AchievementUnlockedEvent>,
+    query_toast: Query<Entity, With<ToastTimer>>,
+) {
+    // New toasts line up under the ones still shown
+    let shown = query_toast.iter().count()..;
+    for (shown, event) in shown.zip(unlocked_event.iter()) {
+        let achievement = &achievements.list[event.index];
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(380.0), Val::Px(TOAST_HEIGHT - 10.0)),
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            right: Val::Px(20.0),
+                            top: toast_top(shown),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    z_index: ZIndex::Global(10),
+                    ..default()
+                },
+                Toast,
+                ToastTimer(Timer::from_seconds(
+                    ACHIEVEMENT_TOAST_SECONDS,
+                    TimerMode::Once,
+                )),
+            ))
+            .with_children(|parent| {
+                panel(
+                    parent,
+                    Toast,
+                    Style {
+                        size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    UI_PANEL_COLOR,
+                )
+                .with_children(|parent| {
+                    label(
+                        parent,
+                        &asset_server,
+                        Toast,
+                        &localization.format(
+                            "achievements.unlocked",
+                            &[("name", &localization.get(&achievement.name))],
+                        ),
+                        24.0,
+                        TITLE_COLOR,
+                    );
+                    label(
+                        parent,
+                        &asset_server,
+                        Toast,
+                        localization.get(&achievement.description),
+                        18.0,
+                        UI_TEXT_COLOR,
+                    );
+                });
+            });
+    }
+}
+
+pub fn achievement_toast_update(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query_toast: Query<(Entity, &mut ToastTimer, &mut Style)>,
+) {
+    let mut shown = Vec::new();
+    for (entity, mut timer, style) in &mut query_toast {
+        if timer.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        } else {
+            shown.push((timer.0.elapsed(), entity, style));
+        }
+    }
+    // The toasts left move up into the slots expired ones freed, oldest on top
+    shown.sort_by(|(a, a_entity, _), (b, b_entity, _)| b.cmp(a).then(a_entity.cmp(b_entity)));
+    for (slot, (_, _, mut style)) in shown.into_iter().enumerate() {
+        let top = toast_top(slot);
+        if style.position.top != top {
+            style.position.top = top;
+        }
+    }
+}
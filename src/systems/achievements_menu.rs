@@ -0,0 +1,154 @@
+use bevy::{
+    prelude::{
+        AssetServer, BuildChildren, Camera2dBundle, ChildBuilder, Color, Commands, Entity,
+        NodeBundle, Query, Res, With,
+    },
+    ui::{AlignItems, FlexDirection, JustifyContent, Size, Style, Val},
+    utils::default,
+};
+
+use crate::{
+    components::{AchievementsMenu, LocalizedText, UiAction, UiBackAction},
+    constants::{UI_PANEL_COLOR, UI_TEXT_COLOR},
+    resource::{Achievement, AchievementProgress, Achievements, Localization},
+    systems::ui::{label, panel, text_button},
+};
+
+const LOCKED_COLOR: Color = Color::rgb(0.45, 0.45, 0.45);
+
+fn achievement_layout(
+    asset_server: &Res<AssetServer>,
+    localization: &Localization,
+    parent: &mut ChildBuilder,
+    achievement: &Achievement,
+    unlocked: bool,
+) {
+    let color = if unlocked {
+        UI_TEXT_COLOR
+    } else {
+        LOCKED_COLOR
+    };
+    parent
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(90.0), Val::Auto),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+            AchievementsMenu,
+        ))
+        .with_children(|parent| {
+            label(
+                parent,
+                asset_server,
+                AchievementsMenu,
+                localization.get(&achievement.name),
+                28.0,
+                color,
+            );
+            label(
+                parent,
+                asset_server,
+                AchievementsMenu,
+                localization.get(&achievement.description),
+                18.0,
+                color,
+            );
+        });
+}
+
+pub fn setup_achievements_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    achievements: Res<Achievements>,
+    progress: Res<AchievementProgress>,
+    localization: Res<Localization>,
+) {
+    commands.spawn((Camera2dBundle::default(), AchievementsMenu));
+    let is_unlocked = |achievement: &Achievement| progress.unlocked.contains(&achievement.id);
+    let unlocked = achievements
+        .list
+        .iter()
+        .filter(|achievement| is_unlocked(achievement))
+        .count();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            AchievementsMenu,
+            UiBackAction(UiAction::BackToMainMenu),
+        ))
+        .with_children(|parent| {
+            panel(
+                parent,
+                AchievementsMenu,
+                Style {
+                    size: Size::new(Val::Percent(70.0), Val::Percent(90.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::SpaceAround,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                UI_PANEL_COLOR,
+            )
+            .with_children(|parent| {
+                label(
+                    parent,
+                    &asset_server,
+                    AchievementsMenu,
+                    localization.get("achievements.title"),
+                    40.0,
+                    UI_TEXT_COLOR,
+                )
+                .insert(LocalizedText("achievements.title"));
+                label(
+                    parent,
+                    &asset_server,
+                    AchievementsMenu,
+                    &localization.format(
+                        "achievements.count",
+                        &[("unlocked", &unlocked), ("total", &achievements.list.len())],
+                    ),
+                    20.0,
+                    UI_TEXT_COLOR,
+                );
+                for achievement in &achievements.list {
+                    achievement_layout(
+                        &asset_server,
+                        &localization,
+                        parent,
+                        achievement,
+                        is_unlocked(achievement),
+                    );
+                }
+                text_button(
+                    parent,
+                    &asset_server,
+                    AchievementsMenu,
+                    UiAction::BackToMainMenu,
+                    Size::new(Val::Px(150.0), Val::Px(50.0)),
+                    localization.get("achievements.back"),
+                    LocalizedText("achievements.back"),
+                );
+            });
+        });
+}
+
+pub fn clean_achievements_menu(
+    mut commands: Commands,
+    achievements_menu_query: Query<Entity, With<AchievementsMenu>>,
+) {
+    for entity in achievements_menu_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
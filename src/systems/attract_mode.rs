@@ -0,0 +1,172 @@
+use std::mem;
+
+use bevy::{
+    ecs::system::SystemParam,
+    input::mouse::MouseMotion,
+    prelude::{
+        Commands, Entity, EventReader, GamepadButton, Input, KeyCode, MouseButton, NextState,
+        Query, Res, ResMut, TextBundle, With,
+    },
+    text::TextStyle,
+    time::Time,
+    ui::{PositionType, Style, UiRect, Val},
+    utils::default,
+};
+
+use crate::{
+    components::{Chunk, InGame},
+    constants::ATTRACT_TEXT_COLOR,
+    resource::{
+        AttractMode, Bot, BotMovement, BotParents, ChunksMap, EndlessCurve, GameAssets, GameRng,
+        Localization, Party, Persistence, RunSnapshot, Score, TotalKilled, TotalSpawned,
+        TotalToSpawn,
+    },
+    AppState,
+};
+
+#[derive(SystemParam)]
+pub struct AnyInput<'w, 's> {
+    keys: Res<'w, Input<KeyCode>>,
+    mouse_buttons: Res<'w, Input<MouseButton>>,
+    gamepad_buttons: Res<'w, Input<GamepadButton>>,
+    mouse_motion: EventReader<'w, 's, MouseMotion>,
+}
+
+impl AnyInput<'_, '_> {
+    pub fn happened(&mut self) -> bool {
+        let moved = !self.mouse_motion.is_empty();
+        self.mouse_motion.clear();
+        moved
+            || self.keys.get_just_pressed().next().is_some()
+            || self.mouse_buttons.get_just_pressed().next().is_some()
+            || self.gamepad_buttons.get_just_pressed().next().is_some()
+    }
+}
+
+// The resources a game writes to, swapped as a whole between the player's run and the demo
+#[derive(SystemParam)]
+pub struct RunResources<'w> {
+    score: ResMut<'w, Score>,
+    party: ResMut<'w, Party>,
+    total_to_spawn: ResMut<'w, TotalToSpawn>,
+    total_killed: ResMut<'w, TotalKilled>,
+    total_spawned: ResMut<'w, TotalSpawned>,
+    rng: ResMut<'w, GameRng>,
+    persistence: ResMut<'w, Persistence>,
+}
+
+impl RunResources<'_> {
+    fn swap(&mut self, run: RunSnapshot) -> RunSnapshot {
+        RunSnapshot {
+            score: mem::replace(&mut *self.score, run.score),
+            party: mem::replace(&mut *self.party, run.party),
+            total_to_spawn: mem::replace(&mut *self.total_to_spawn, run.total_to_spawn),
+            total_killed: mem::replace(&mut *self.total_killed, run.total_killed),
+            total_spawned: mem::replace(&mut *self.total_spawned, run.total_spawned),
+            rng: mem::replace(&mut *self.rng, run.rng),
+            persistence: mem::replace(&mut self.persistence.enabled, run.persistence),
+        }
+    }
+}
+
+// Start a demo wave once the main menu has been left alone long enough
+pub fn attract_idle_update(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut input: AnyInput,
+    mut attract_mode: ResMut<AttractMode>,
+    mut app_state: ResMut<NextState<AppState>>,
+    mut run: RunResources,
+    endless_curve: Res<EndlessCurve>,
+) {
+    if input.happened() {
+        attract_mode.idle.reset();
+        return;
+    }
+    if !attract_mode.idle.tick(time.delta()).just_finished() {
+        return;
+    }
+    let difficulty = run.score.difficulty();
+    let mut total_to_spawn = TotalToSpawn::default();
+    total_to_spawn.update_paramter_for_level_id(0, difficulty, &endless_curve);
+    let saved_run = run.swap(RunSnapshot {
+        score: Score::new(difficulty),
+        party: Party::new(1),
+        total_to_spawn,
+        total_killed: TotalKilled::default(),
+        total_spawned: TotalSpawned::default(),
+        rng: GameRng::new(None),
+        persistence: false,
+    });
+    attract_mode.saved_run = Some(saved_run);
+    commands.insert_resource(Bot {
+        movement: BotMovement::KiteCircles,
+        parents: BotParents::Random,
+        max_level: None,
+    });
+    app_state.set(AppState::InGame);
+}
+
+pub fn setup_attract_overlay(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    localization: Res<Localization>,
+) {
+    commands.spawn((
+        TextBundle::from_section(
+            localization.get("attract.demo"),
+            TextStyle {
+                font: assets.font.clone(),
+                font_size: 30.0,
+                color: ATTRACT_TEXT_COLOR,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                bottom: Val::Px(20.0),
+                left: Val::Px(20.0),
+                ..default()
+            },
+            ..default()
+        }),
+        InGame,
+    ));
+}
+
+// Back to the menu on any input, and before the demo reaches the parent choice or the game over
+// screen so it never shows up in the high scores
+pub fn attract_demo_update(mut input: AnyInput, mut app_state: ResMut<NextState<AppState>>) {
+    let demo_over = matches!(
+        app_state.0,
+        Some(AppState::LevelMenu) | Some(AppState::RetryMenu)
+    );
+    if input.happened() || demo_over {
+        app_state.set(AppState::MainMenu);
+    }
+}
+
+// Give the player's run back once the demo is over
+pub fn end_attract_demo(
+    mut commands: Commands,
+    mut attract_mode: ResMut<AttractMode>,
+    mut run: RunResources,
+    query_chunks: Query<Entity, With<Chunk>>,
+    mut chunk_map_resource: ResMut<ChunksMap>,
+) {
+    attract_mode.idle.reset();
+    let Some(saved_run) = attract_mode.saved_run.take() else {
+        return;
+    };
+    run.swap(saved_run);
+    commands.remove_resource::<Bot>();
+    // The demo map would otherwise be kept by a run starting past the first wave
+    for chunk in query_chunks.iter() {
+        commands.entity(chunk).despawn();
+    }
+    chunk_map_resource.chunks.clear();
+}
+
+pub fn attract_demo_running(attract_mode: Res<AttractMode>) -> bool {
+    attract_mode.saved_run.is_some()
+}
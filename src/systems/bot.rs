@@ -0,0 +1,109 @@
+use bevy::{
+    prelude::{NextState, Query, Res, ResMut, Transform, Vec2, With, Without},
+    time::Time,
+};
+use rand::Rng;
+
+use crate::{
+    components::{Aim, Debuff, Enemy, Move, Player, PlayerColor, Weapon},
+    constants::{BOT_KITE_RADIUS, PLAYER_POSITION},
+    resource::{Bot, BotLog, BotMovement, BotParents, GameRng, Score},
+    systems::{in_game::LivingPlayer, level_menu::Heredity},
+    AppState,
+};
+
+// Direction running around the starting point, pulled back onto the circle when drifting away
+pub fn kite_direction(position: Vec2, center: Vec2) -> Vec2 {
+    let offset = position - center;
+    let Some(outward) = offset.try_normalize() else {
+        return Vec2::X;
+    };
+    let correction = (BOT_KITE_RADIUS - offset.length()) / BOT_KITE_RADIUS;
+    (outward.perp() + outward * correction).normalize_or_zero()
+}
+
+// Runs after the player input systems so the bot has the last word on the controls
+pub fn bot_control(
+    bot: Res<Bot>,
+    mut query_player: Query<(&Transform, &mut Move, &mut Aim, &mut Weapon), LivingPlayer>,
+    query_enemy: Query<&Transform, (With<Enemy>, Without<Player>)>,
+) {
+    for (player_transform, mut player_move, mut aim, mut weapon) in &mut query_player {
+        let position = player_transform.translation.truncate();
+        player_move.direction = match bot.movement {
+            BotMovement::KiteCircles => kite_direction(position, PLAYER_POSITION.truncate()),
+            BotMovement::StandStill => Vec2::ZERO,
+        };
+        let nearest_enemy = query_enemy
+            .iter()
+            .map(|transform| transform.translation.truncate())
+            .min_by(|a, b| {
+                a.distance_squared(position)
+                    .total_cmp(&b.distance_squared(position))
+            });
+        if let Some(target) = nearest_enemy {
+            aim.direction = (target - position).normalize_or_zero();
+        }
+        weapon.is_firing = nearest_enemy.is_some();
+    }
+}
+
+pub fn bot_start_wave(time: Res<Time>, mut bot_log: ResMut<BotLog>) {
+    bot_log.wave_started = time.elapsed_seconds();
+}
+
+pub fn bot_end_wave(time: Res<Time>, mut bot_log: ResMut<BotLog>) {
+    let seconds = time.elapsed_seconds() - bot_log.wave_started;
+    bot_log.current.wave_seconds.push(seconds);
+}
+
+pub fn bot_pick_parent(
+    bot: Res<Bot>,
+    score: Res<Score>,
+    mut app_state: ResMut<NextState<AppState>>,
+    mut heredity: Heredity,
+    mut bot_log: ResMut<BotLog>,
+    mut rng: ResMut<GameRng>,
+    query_parents: Query<(&Debuff, &PlayerColor)>,
+) {
+    if bot
+        .max_level
+        .is_some_and(|max_level| score.get_level_index() >= max_level)
+    {
+        app_state.set(AppState::RetryMenu);
+        return;
+    }
+    let parents: Vec<(&Debuff, &PlayerColor)> = query_parents.iter().collect();
+    if parents.is_empty() {
+        return;
+    }
+    let picked = match bot.parents {
+        BotParents::Random => parents[rng.gen_range(0..parents.len())],
+        BotParents::Avoid(flaw) => parents
+            .iter()
+            .copied()
+            .find(|(debuff, _)| !debuff.contains(flaw))
+            .unwrap_or(parents[0]),
+    };
+    let (debuff, color) = picked;
+    // The bot plays every player of the party with the same parents
+    for player in 0..heredity.party.count() {
+        heredity.inherit(player, debuff, color.0);
+    }
+    bot_log.current.debuffs.push(*debuff.get_defaults());
+    app_state.set(AppState::InGame);
+}
+
+pub fn bot_record_run(bot: Res<Bot>, score: Res<Score>, mut bot_log: ResMut<BotLog>) {
+    let mut run = std::mem::take(&mut bot_log.current);
+    run.level = score.get_level_index();
+    run.date = score.get_date();
+    run.survived = bot
+        .max_level
+        .is_some_and(|max_level| run.level >= max_level);
+    bot_log.runs.push(run);
+}
+
+pub fn bot_retry(mut app_state: ResMut<NextState<AppState>>) {
+    app_state.set(AppState::InGame);
+}
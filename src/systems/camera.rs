@@ -0,0 +1,115 @@
+use bevy::prelude::{
+    EventReader, OrthographicProjection, Query, Res, Transform, Vec2, With, Without,
+};
+use bevy::time::Time;
+
+use crate::{
+    components::{Aim, CameraDirector, Downed, Enemy, MainCamera, Player},
+    constants::{
+        CAMERA_DENSITY_ENEMIES, CAMERA_DENSITY_MAX_ZOOM, CAMERA_FRAME_MARGIN, CAMERA_MAX_SHAKE,
+        CAMERA_SHAKE_FREQUENCY, CAMERA_TRAUMA_DECAY,
+    },
+    resource::Settings,
+};
+
+// Shakes the main camera, the amount adds up with the trauma already there
+pub struct CameraTraumaEvent {
+    pub trauma: f32,
+}
+
+// Keeps the camera query apart from the player transforms
+type MainCameraOnly = (With<MainCamera>, Without<Player>);
+
+// Smooth noise between -1.0 and 1.0, a different curve for every seed
+fn shake_noise(time: f32, seed: f32) -> f32 {
+    (time * CAMERA_SHAKE_FREQUENCY + seed).sin()
+        * (time * CAMERA_SHAKE_FREQUENCY * 0.63 + seed * 2.1).cos()
+}
+
+pub fn camera_trauma_update(
+    mut trauma_events: EventReader<CameraTraumaEvent>,
+    settings: Res<Settings>,
+    mut query_camera: Query<&mut CameraDirector, With<MainCamera>>,
+) {
+    let trauma: f32 = trauma_events.iter().map(|event| event.trauma).sum();
+    // Nothing shakes for players sensitive to motion
+    if trauma <= 0.0 || settings.accessibility.reduce_motion {
+        return;
+    }
+    for mut director in &mut query_camera {
+        director.trauma = (director.trauma + trauma).min(1.0);
+    }
+}
+
+// Follow the players still fighting ahead of their aim, zoom out to keep them all on screen and
+// as the crowd around them grows
+pub fn camera_director_update(
+    time: Res<Time>,
+    mut query_camera: Query<
+        (
+            &mut Transform,
+            &mut OrthographicProjection,
+            &mut CameraDirector,
+        ),
+        MainCameraOnly,
+    >,
+    query: Query<(&Transform, &Aim, Option<&Downed>), With<Player>>,
+    query_enemy: Query<&Transform, (With<Enemy>, Without<MainCamera>)>,
+) {
+    let living = query
+        .iter()
+        .filter(|(_, _, downed)| downed.is_none())
+        .count();
+    let framed = || {
+        query
+            .iter()
+            .filter(|(_, _, downed)| living == 0 || downed.is_none())
+    };
+    let mut positions = framed().map(|(transform, _, _)| transform.translation.truncate());
+    let Some(first) = positions.next() else {
+        return;
+    };
+    let (min, max) = positions.fold((first, first), |(min, max), position| {
+        (min.min(position), max.max(position))
+    });
+    let aim = framed()
+        .map(|(_, aim, _)| aim.direction)
+        .sum::<Vec2>()
+        .normalize_or_zero();
+    let delta = time.delta_seconds();
+    for (mut camera_transform, mut projection, mut director) in &mut query_camera {
+        let target = (min + max) / 2.0 + aim * director.look_ahead;
+        let follow = (director.follow_speed * delta).min(1.0);
+        let focus = director.focus;
+        director.focus = focus + (target - focus) * follow;
+
+        let shake = director.trauma * director.trauma * CAMERA_MAX_SHAKE;
+        let now = time.elapsed_seconds();
+        let offset = Vec2::new(shake_noise(now, 0.0), shake_noise(now, 10.0)) * shake;
+        camera_transform.translation.x = director.focus.x + offset.x;
+        camera_transform.translation.y = director.focus.y + offset.y;
+        director.trauma = (director.trauma - CAMERA_TRAUMA_DECAY * delta).max(0.0);
+
+        let view = projection.area.size() / projection.scale;
+        if view.min_element() <= 0.0 {
+            continue;
+        }
+        let frame = ((max - min + 2.0 * CAMERA_FRAME_MARGIN) / view).max_element();
+        // Enemies within the unzoomed view of the players
+        let radius_squared = (view / 2.0).length_squared();
+        let crowd = query_enemy
+            .iter()
+            .filter(|transform| {
+                transform
+                    .translation
+                    .truncate()
+                    .distance_squared(director.focus)
+                    < radius_squared
+            })
+            .count() as f32;
+        let density =
+            1.0 + (crowd / CAMERA_DENSITY_ENEMIES).min(1.0) * (CAMERA_DENSITY_MAX_ZOOM - 1.0);
+        let scale = frame.max(density).max(1.0);
+        projection.scale += (scale - projection.scale) * (director.zoom_speed * delta).min(1.0);
+    }
+}
@@ -0,0 +1,123 @@
+use bevy::prelude::{EventReader, Handle, Query, Res, ResMut, Transform, Vec2, Vec3, With};
+use bevy_kira_audio::prelude::*;
+
+use crate::{
+    components::MainCamera,
+    constants::{MAX_SFX_VOICES, SPATIAL_HEARING_RADIUS, SPATIAL_MIN_VOLUME, SPATIAL_PAN_WIDTH},
+    resource::{GameAssets, SfxChannel, SfxVoices},
+};
+
+#[derive(Clone, Copy)]
+pub enum CombatSound {
+    Hit,
+    Death,
+    Spawn,
+}
+
+pub struct CombatSoundEvent {
+    pub sound: CombatSound,
+    pub position: Vec3,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SpatialSound {
+    pub volume: f64,
+    // 0.0 is full left, 0.5 centered and 1.0 full right
+    pub panning: f64,
+}
+
+// Volume and panning of a sound heard by a listener, quieter with distance and panned on the horizontal offset
+pub fn spatial_sound(source: Vec2, listener: Vec2) -> SpatialSound {
+    let offset = source - listener;
+    let attenuation = 1.0 - (offset.length() / SPATIAL_HEARING_RADIUS).min(1.0);
+    let pan = (offset.x / SPATIAL_PAN_WIDTH).clamp(-1.0, 1.0);
+    SpatialSound {
+        volume: (SPATIAL_MIN_VOLUME + (1.0 - SPATIAL_MIN_VOLUME) * attenuation) as f64,
+        panning: (0.5 + pan * 0.5) as f64,
+    }
+}
+
+// Play a sound effect unless the SFX channel already has all its voices busy
+pub fn play_sfx(
+    sfx_channel: &AudioChannel<SfxChannel>,
+    sfx_voices: &mut SfxVoices,
+    sound: Handle<AudioSource>,
+    spatial: SpatialSound,
+    playback_rate: f64,
+) {
+    sfx_voices
+        .instances
+        .retain(|instance| !matches!(sfx_channel.state(instance), PlaybackState::Stopped));
+    if sfx_voices.instances.len() < MAX_SFX_VOICES {
+        let instance = sfx_channel
+            .play(sound)
+            .with_volume(spatial.volume)
+            .with_panning(spatial.panning)
+            .with_playback_rate(playback_rate)
+            .handle();
+        sfx_voices.instances.push(instance);
+    }
+}
+
+pub fn play_combat_sounds(
+    mut combat_sound_events: EventReader<CombatSoundEvent>,
+    query_camera: Query<&Transform, With<MainCamera>>,
+    assets: Res<GameAssets>,
+    sfx_channel: Res<AudioChannel<SfxChannel>>,
+    mut sfx_voices: ResMut<SfxVoices>,
+) {
+    let Ok(camera_transform) = query_camera.get_single() else {
+        combat_sound_events.clear();
+        return;
+    };
+    let listener = camera_transform.translation.truncate();
+    for event in combat_sound_events.iter() {
+        let mut spatial = spatial_sound(event.position.truncate(), listener);
+        // Every combat sound share the same sample, pitched to tell them apart
+        let playback_rate = match event.sound {
+            CombatSound::Hit => 1.0,
+            CombatSound::Death => 0.6,
+            CombatSound::Spawn => {
+                spatial.volume *= 0.5;
+                1.6
+            }
+        };
+        play_sfx(
+            &sfx_channel,
+            &mut sfx_voices,
+            assets.hit_sound.clone(),
+            spatial,
+            playback_rate,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sound_on_listener_is_centered_and_loud() {
+        let spatial = spatial_sound(Vec2::new(10.0, 10.0), Vec2::new(10.0, 10.0));
+        assert_eq!(spatial.volume, 1.0);
+        assert_eq!(spatial.panning, 0.5);
+    }
+
+    #[test]
+    fn sound_on_the_left_is_panned_left() {
+        let spatial = spatial_sound(Vec2::new(-SPATIAL_PAN_WIDTH * 2.0, 0.0), Vec2::ZERO);
+        assert_eq!(spatial.panning, 0.0);
+        let spatial = spatial_sound(Vec2::new(SPATIAL_PAN_WIDTH / 2.0, 0.0), Vec2::ZERO);
+        assert!((spatial.panning - 0.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn volume_decreases_with_distance_down_to_the_floor() {
+        let near = spatial_sound(Vec2::new(0.0, 100.0), Vec2::ZERO);
+        let far = spatial_sound(Vec2::new(0.0, 800.0), Vec2::ZERO);
+        let out_of_range = spatial_sound(Vec2::new(0.0, SPATIAL_HEARING_RADIUS * 3.0), Vec2::ZERO);
+        assert!(near.volume > far.volume);
+        assert!(far.volume > out_of_range.volume);
+        assert!((out_of_range.volume - SPATIAL_MIN_VOLUME as f64).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,122 @@
+use bevy::{
+    prelude::{Commands, Entity, EventReader, Query, Res, Text2dBundle, Transform, Vec3, With},
+    text::{Text, TextAlignment, TextStyle},
+    time::Time,
+    utils::default,
+};
+
+use crate::{
+    components::{FloatingText, InGame},
+    constants::{
+        COMBAT_TEXT_Z, DAMAGE_NUMBER_BUDGET, DAMAGE_NUMBER_COLOR, DAMAGE_NUMBER_FONT_SIZE,
+        DAMAGE_NUMBER_SCALE, DAMAGE_NUMBER_SECONDS, DAMAGE_NUMBER_SPEED, KILL_NUMBER_COLOR,
+        KILL_NUMBER_FONT_SIZE, KILL_NUMBER_SECONDS, KILL_NUMBER_SPEED,
+    },
+    resource::{GameAssets, Settings},
+};
+
+pub struct DamageEvent {
+    pub position: Vec3,
+    pub damage: f32,
+    // The hit that took the last of the enemy health
+    pub killing_blow: bool,
+}
+
+pub fn damage_text(damage: f32) -> String {
+    format!("{}", (damage * DAMAGE_NUMBER_SCALE).round() as i32)
+}
+
+pub fn spawn_damage_numbers(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageEvent>,
+    assets: Res<GameAssets>,
+    settings: Res<Settings>,
+    query: Query<(), With<FloatingText>>,
+) {
+    if !settings.hud.damage_numbers {
+        damage_events.clear();
+        return;
+    }
+    let mut live = query.iter().count();
+    for event in damage_events.iter() {
+        // Killing blows are always shown, the budget only drops plain hits
+        if live >= DAMAGE_NUMBER_BUDGET && !event.killing_blow {
+            continue;
+        }
+        let (text, font_size, color, lifetime, speed) = if event.killing_blow {
+            (
+                format!("{}!", damage_text(event.damage)),
+                KILL_NUMBER_FONT_SIZE,
+                KILL_NUMBER_COLOR,
+                KILL_NUMBER_SECONDS,
+                KILL_NUMBER_SPEED,
+            )
+        } else {
+            (
+                damage_text(event.damage),
+                DAMAGE_NUMBER_FONT_SIZE,
+                DAMAGE_NUMBER_COLOR,
+                DAMAGE_NUMBER_SECONDS,
+                DAMAGE_NUMBER_SPEED,
+            )
+        };
+        // Spawned at the base size, apply_text_scale scales it like any other text
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    text,
+                    TextStyle {
+                        font: assets.font.clone(),
+                        font_size,
+                        color,
+                    },
+                )
+                .with_alignment(TextAlignment::Center),
+                transform: Transform::from_translation(
+                    event.position.truncate().extend(COMBAT_TEXT_Z),
+                ),
+                ..default()
+            },
+            FloatingText {
+                age: 0.0,
+                lifetime,
+                speed,
+                color,
+            },
+            InGame,
+        ));
+        live += 1;
+    }
+}
+
+pub fn floating_text_update(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut FloatingText, &mut Transform, &mut Text)>,
+) {
+    let delta = time.delta_seconds();
+    query.for_each_mut(|(entity, mut floating, mut transform, mut text)| {
+        floating.age += delta;
+        if floating.age >= floating.lifetime {
+            commands.entity(entity).despawn();
+            return;
+        }
+        transform.translation.y += floating.speed * delta;
+        // Fully visible for the first half of its life
+        let fade = (2.0 * (1.0 - floating.age / floating.lifetime)).min(1.0);
+        let color = floating.color;
+        text.sections[0].style.color = color.with_a(color.a() * fade);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::PLAYER_DAMAGE;
+
+    #[test]
+    fn weakened_damage_shows_as_a_whole_number() {
+        assert_eq!(damage_text(PLAYER_DAMAGE), "50");
+        assert_eq!(damage_text(PLAYER_DAMAGE * 0.7), "35");
+    }
+}
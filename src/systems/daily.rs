@@ -0,0 +1,146 @@
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::{Commands, EventReader, NextState, Res, ResMut},
+};
+
+use crate::{
+    components::UiAction,
+    resource::{
+        calendar_date, DailyRun, DailyScoreEntry, DailyScores, EndlessCurve, GameRng, Localization,
+        Party, Persistence, Score, TotalToSpawn,
+    },
+    systems::ui::UiClickEvent,
+    AppState, Difficulty,
+};
+
+// What a daily run resets so every player starts it the same way, and restores once it ends
+#[derive(SystemParam)]
+pub struct RunSetup<'w> {
+    party: ResMut<'w, Party>,
+    score: ResMut<'w, Score>,
+    total_to_spawn: ResMut<'w, TotalToSpawn>,
+    endless_curve: Res<'w, EndlessCurve>,
+}
+
+// Daily runs are played solo on the normal difficulty
+pub fn daily_button(
+    mut commands: Commands,
+    mut click_event: EventReader<UiClickEvent>,
+    mut app_state: ResMut<NextState<AppState>>,
+    daily_scores: Res<DailyScores>,
+    mut setup: RunSetup,
+) {
+    for event in click_event.iter() {
+        if let UiAction::StartDaily = event.action {
+            let day = DailyRun::today();
+            commands.insert_resource(DailyRun {
+                day,
+                scored: !daily_scores.attempted(day),
+                menu_players: setup.party.count(),
+                menu_difficulty: setup.score.difficulty(),
+            });
+            *setup.party = Party::new(1);
+            *setup.score = Score::new(Difficulty::Normal);
+            setup.total_to_spawn.update_paramter_for_level_id(
+                0,
+                Difficulty::Normal,
+                &setup.endless_curve,
+            );
+            app_state.set(AppState::PreStartMenu);
+        }
+    }
+}
+
+// Back on the main menu the next run is a regular one, with the players and difficulty picked
+// before the daily run
+pub fn end_daily_run(mut commands: Commands, daily: Option<Res<DailyRun>>, mut setup: RunSetup) {
+    let Some(daily) = daily else {
+        return;
+    };
+    *setup.party = Party::new(daily.menu_players);
+    *setup.score = Score::new(daily.menu_difficulty);
+    setup.total_to_spawn.update_paramter_for_level_id(
+        0,
+        daily.menu_difficulty,
+        &setup.endless_curve,
+    );
+    commands.remove_resource::<DailyRun>();
+}
+
+// The attempt of the day is used as soon as its first wave starts, quitting does not give it back
+pub fn daily_wave_seed(
+    daily: Res<DailyRun>,
+    score: Res<Score>,
+    mut rng: ResMut<GameRng>,
+    mut daily_scores: ResMut<DailyScores>,
+    persistence: Res<Persistence>,
+) {
+    let level = score.get_level_index();
+    *rng = GameRng::new(Some(daily.wave_seed(level)));
+    if level == 0 && daily.scored && !daily_scores.attempted(daily.day) {
+        daily_scores.last_attempt = Some(daily.day);
+        if persistence.enabled {
+            daily_scores.save();
+        }
+    }
+}
+
+pub fn daily_parents_seed(daily: Res<DailyRun>, score: Res<Score>, mut rng: ResMut<GameRng>) {
+    *rng = GameRng::new(Some(daily.parents_seed(score.get_level_index())));
+}
+
+// Daily runs go on their own leaderboard instead of the high scores
+#[derive(SystemParam)]
+pub struct DailyBoard<'w> {
+    run: Option<ResMut<'w, DailyRun>>,
+    scores: ResMut<'w, DailyScores>,
+}
+
+impl DailyBoard<'_> {
+    pub fn is_daily(&self) -> bool {
+        self.run.is_some()
+    }
+
+    // Retrying the day is practice, only the first attempt is scored
+    pub fn record(&mut self, score: &Score, persistence: &Persistence) -> bool {
+        let Some(run) = &mut self.run else {
+            return false;
+        };
+        let scored = run.scored;
+        if scored {
+            self.scores.insert(DailyScoreEntry {
+                day: run.day,
+                date: score.get_date(),
+                level: score.get_level_index(),
+            });
+            if persistence.enabled {
+                self.scores.save();
+            }
+        }
+        run.scored = false;
+        scored
+    }
+
+    pub fn text(&self, localization: &Localization, scored: bool) -> String {
+        let Some(run) = &self.run else {
+            return String::new();
+        };
+        let mut lines =
+            vec![localization.format("retry.daily_title", &[("day", &calendar_date(run.day))])];
+        if !scored {
+            lines.push(localization.get("retry.daily_practice").to_string());
+        }
+        lines.extend(self.scores.entries.iter().enumerate().map(|(i, entry)| {
+            localization.format(
+                "retry.daily_score",
+                &[
+                    ("rank", &(i + 1)),
+                    ("day", &calendar_date(entry.day)),
+                    ("date", &entry.date),
+                    ("wave", &(entry.level + 1)),
+                ],
+            )
+        }));
+        lines.join("\n")
+    }
+}
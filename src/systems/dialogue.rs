@@ -0,0 +1,233 @@
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::{
+        AssetServer, BuildChildren, Color, Commands, DespawnRecursiveExt, Entity, ImageBundle,
+        Input, MouseButton, NodeBundle, Query, Res, ResMut, With,
+    },
+    text::Text,
+    time::Time,
+    ui::{
+        AlignItems, Display, FlexDirection, FocusPolicy, JustifyContent, PositionType, Size, Style,
+        UiImage, UiRect, Val,
+    },
+    utils::default,
+};
+
+use crate::{
+    components::{Dialogue, DialoguePortrait, DialogueText, LevelMenu, SpokenLine, UiButton},
+    constants::{DIALOGUE_CHARS_PER_SECOND, UI_PANEL_COLOR, UI_TEXT_COLOR},
+    resource::{
+        Bot, Eras, GameAssets, Localization, Party, Persistence, Score, Story, StoryMoment,
+        StoryProgress,
+    },
+    systems::ui::{label, panel, MenuInput},
+};
+
+const SPEAKER_COLOR: Color = Color::rgb(0.9, 0.8, 0.3);
+
+#[derive(SystemParam)]
+pub struct StoryContext<'w> {
+    story: Res<'w, Story>,
+    progress: ResMut<'w, StoryProgress>,
+    score: Res<'w, Score>,
+    eras: Res<'w, Eras>,
+    party: Res<'w, Party>,
+    persistence: Res<'w, Persistence>,
+    bot: Option<Res<'w, Bot>>,
+}
+
+// Play the first story beat matching the run over the level menu
+pub fn start_story_beat(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    assets: Res<GameAssets>,
+    localization: Res<Localization>,
+    mut context: StoryContext,
+) {
+    // Bot and demo runs skip the story, co-op players after the first one already heard it
+    if context.bot.is_some() || context.party.picking > 0 {
+        return;
+    }
+    let level = context.score.get_level_index();
+    let moment = StoryMoment {
+        level,
+        era: context.eras.index_at(level),
+        era_start: context.eras.starts_era(level),
+        flaws: &context.party.flaws,
+        deaths: context.progress.deaths,
+    };
+    let Some(beat) = context.story.next_beat(&moment, &context.progress.seen) else {
+        return;
+    };
+    let era = context.eras.at(level);
+    let (era_name, lore) = (localization.get(&era.name), localization.get(&era.lore));
+    let lines: Vec<SpokenLine> = beat
+        .lines
+        .iter()
+        .map(|line| SpokenLine {
+            speaker: localization.get(&line.speaker).to_string(),
+            portrait: line
+                .portrait
+                .as_ref()
+                .and_then(|path| assets.portraits.get(path).cloned()),
+            text: localization.format(
+                &line.text,
+                &[
+                    ("era", &era_name),
+                    ("lore", &lore),
+                    ("year", &context.score.get_date()),
+                    ("wave", &(level + 1)),
+                    ("deaths", &context.progress.deaths),
+                ],
+            ),
+        })
+        .collect();
+    if beat.once {
+        context.progress.seen.insert(beat.id.clone());
+        if context.persistence.enabled {
+            context.progress.save();
+        }
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::FlexEnd,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                // The parent buttons below are out of reach until the dialogue ends
+                focus_policy: FocusPolicy::Block,
+                ..default()
+            },
+            Dialogue {
+                lines,
+                current: 0,
+                shown: 0.0,
+            },
+            LevelMenu,
+        ))
+        .with_children(|parent| {
+            panel(
+                parent,
+                LevelMenu,
+                Style {
+                    size: Size::new(Val::Percent(80.0), Val::Px(170.0)),
+                    margin: UiRect::bottom(Val::Px(30.0)),
+                    padding: UiRect::all(Val::Px(15.0)),
+                    align_items: AlignItems::Center,
+                    gap: Size::new(Val::Px(20.0), Val::Px(0.0)),
+                    ..default()
+                },
+                UI_PANEL_COLOR,
+            )
+            .with_children(|parent| {
+                parent.spawn((
+                    ImageBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(96.0), Val::Px(96.0)),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    DialoguePortrait,
+                    LevelMenu,
+                ));
+                parent
+                    .spawn((
+                        NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                                flex_direction: FlexDirection::Column,
+                                justify_content: JustifyContent::SpaceBetween,
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        LevelMenu,
+                    ))
+                    .with_children(|parent| {
+                        label(parent, &asset_server, LevelMenu, "", 26.0, SPEAKER_COLOR)
+                            .insert(DialogueText::Speaker);
+                        label(parent, &asset_server, LevelMenu, "", 20.0, UI_TEXT_COLOR)
+                            .insert(DialogueText::Line);
+                        label(
+                            parent,
+                            &asset_server,
+                            LevelMenu,
+                            localization.get("dialogue.next"),
+                            14.0,
+                            UI_TEXT_COLOR,
+                        );
+                    });
+            });
+        });
+}
+
+#[derive(SystemParam)]
+pub struct DialogueWidgets<'w, 's> {
+    query_text: Query<'w, 's, (&'static mut Text, &'static DialogueText)>,
+    query_portrait:
+        Query<'w, 's, (&'static mut UiImage, &'static mut Style), With<DialoguePortrait>>,
+    query_button: Query<'w, 's, &'static mut UiButton, With<LevelMenu>>,
+}
+
+// Type the current line out, a press shows it whole then moves on to the next one
+pub fn dialogue_update(
+    mut commands: Commands,
+    time: Res<Time>,
+    input: MenuInput,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut query_dialogue: Query<(Entity, &mut Dialogue)>,
+    mut widgets: DialogueWidgets,
+) {
+    let Ok((entity, mut dialogue)) = query_dialogue.get_single_mut() else {
+        return;
+    };
+    let length = dialogue.lines[dialogue.current].text.chars().count();
+    if input.activate() || mouse_buttons.just_pressed(MouseButton::Left) {
+        if (dialogue.shown as usize) < length {
+            dialogue.shown = length as f32;
+        } else if dialogue.current + 1 < dialogue.lines.len() {
+            dialogue.current += 1;
+            dialogue.shown = 0.0;
+        } else {
+            commands.entity(entity).despawn_recursive();
+            for mut button in &mut widgets.query_button {
+                button.disabled = false;
+            }
+            return;
+        }
+    } else {
+        dialogue.shown =
+            (dialogue.shown + DIALOGUE_CHARS_PER_SECOND * time.delta_seconds()).min(length as f32);
+    }
+    for mut button in &mut widgets.query_button {
+        button.disabled = true;
+    }
+
+    let line = &dialogue.lines[dialogue.current];
+    for (mut text, part) in &mut widgets.query_text {
+        let value = match part {
+            DialogueText::Speaker => line.speaker.clone(),
+            DialogueText::Line => line.text.chars().take(dialogue.shown as usize).collect(),
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+    for (mut image, mut style) in &mut widgets.query_portrait {
+        match &line.portrait {
+            Some(portrait) => {
+                image.texture = portrait.clone();
+                style.display = Display::Flex;
+            }
+            None => style.display = Display::None,
+        }
+    }
+}
@@ -1,39 +1,56 @@
 use bevy::{
+    ecs::system::SystemParam,
     prelude::{
-        AssetServer, Assets, Camera2dBundle, Color, Commands, Entity, EventReader, EventWriter,
-        Handle, Input, KeyCode, MouseButton, NextState, OrthographicProjection, Query, Res, ResMut,
-        Transform, Vec2, Vec3, Vec4, With, Without,
-    },
-    sprite::{
-        collide_aabb::collide, Sprite, SpriteBundle, SpriteSheetBundle, TextureAtlas,
-        TextureAtlasSprite,
+        Axis, Camera2dBundle, Color, Commands, Entity, EventReader, EventWriter, Gamepad,
+        GamepadAxis, GamepadAxisType, GamepadButton, GamepadButtonType, Gamepads, Handle, Input,
+        KeyCode, Local, MouseButton, NextState, OrthographicProjection, Query, Res, ResMut,
+        TextureAtlas, Transform, Vec2, Vec3, Vec4, With, Without,
     },
+    sprite::{collide_aabb::collide, Sprite, SpriteBundle, SpriteSheetBundle, TextureAtlasSprite},
     time::{Time, Timer, TimerMode},
-    utils::default,
+    utils::{default, HashSet},
     window::Window,
 };
-use bevy_kira_audio::prelude::*;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
 use crate::{
     components::{
-        Aim, Alive, AnimationTimer, Bullet, BulletBundle, CharacterBundle, Chunk, Collider, Decay,
-        Enemy, EnemyBundle, Harm, HitCount, InGame, MobSpawnerTimer, Move, Player, PlayerBundle,
-        Weapon,
+        Aim, Alive, Animation, Bullet, BulletBundle, CameraDirector, CharacterBundle, Chunk,
+        Collider, Controls, Decay, Downed, Elite, Enemy, EnemyBundle, Harm, HitCount, InGame,
+        MainCamera, MaxHealth, MobSpawnerTimer, Move, Player, PlayerBundle, PlayerSlot, Weapon,
     },
     constants::{
         BULLETS_COLOR, BULLETS_DECAYS, BULLETS_SCALE, BULLETS_SPREAD, BULLET_HEALTH, BULLET_TTL,
-        MAP_SCALE, MOB_COLOR, MOB_COLOR_HURT, MOB_DAMAGE, MOB_HEALTH, MOB_SCALE, MOB_SPAWN_RADIUS,
-        MOB_SPEED, PLAYER_AIM, PLAYER_DIRECTION, PLAYER_POSITION, PLAYER_SCALE, SPEED_SPAWN_BY_LVL,
+        COOP_SPAWN_OFFSET, DOWNED_ALPHA, ELITE_HEALTH_MULTIPLIER, ELITE_SCALE,
+        ELITE_SPEED_MULTIPLIER, GAMEPAD_DEADZONE, MAP_SCALE, MOB_COLOR, MOB_DAMAGE, MOB_HEALTH,
+        MOB_SCALE, MOB_SPAWN_RADIUS, MOB_SPEED, PLAYER_AIM, PLAYER_DIRECTION, PLAYER_POSITION,
+        PLAYER_SCALE, TRAUMA_ENEMY_DEATH, TRAUMA_PLAYER_DOWNED, TRAUMA_PLAYER_HIT,
+    },
+    resource::{
+        AnimationLibrary, AnimationSheet, ChunkType, ChunksMap, ClipName, DifficultyModifiers,
+        EndlessCurve, EnemyKind, Eras, FireMode, FlipRule, GameAssets, GameRng, Party, Score,
+        Settings, TotalKilled, TotalSpawned, TotalToSpawn,
+    },
+    systems::{
+        camera::CameraTraumaEvent,
+        combat_audio::{CombatSound, CombatSoundEvent},
+        combat_text::DamageEvent,
+        particles::{ParticleEffect, ParticleEvent},
     },
-    resource::{ChunkType, ChunksMap, LastShot, Score, TotalKilled, TotalSpawned, TotalToSpawn},
-    AppState, StatsRes,
+    AppState, Difficulty,
 };
 
-pub struct SpawnBulletEvent;
+pub struct SpawnBulletEvent {
+    pub shooter: Entity,
+}
 
 pub struct WaveDoneEvent;
 
+pub struct EnemyKilledEvent {
+    pub position: Vec3,
+    pub elite: bool,
+}
+
 pub struct GameOverEvent;
 
 pub struct CreateMapEvent;
@@ -41,114 +58,149 @@ pub struct CreateMapEvent;
 #[derive(Default)]
 pub struct MobSpawnEvent;
 
+// Players still fighting, downed ones wait for the next wave
+pub type LivingPlayer = (With<Player>, Without<Downed>);
+
+// Pick the clip from the movement and health, hurt plays through once before running again
 pub fn animate_sprite(
     time: Res<Time>,
-    texture_atlases: Res<Assets<TextureAtlas>>,
-    mut query: Query<(
-        &mut AnimationTimer,
-        &mut TextureAtlasSprite,
-        &Move,
-        &Handle<TextureAtlas>,
-        Option<&Player>,
-    )>,
+    library: Res<AnimationLibrary>,
+    mut query: Query<(&mut Animation, &mut TextureAtlasSprite, &Move, &Alive)>,
 ) {
-    for (mut timer, mut sprite, move_component, texture_atlas_handle, maybe_player) in &mut query {
-        if (move_component.direction != Vec2 { x: 0.0, y: 0.0 }) {
-            timer.tick(time.delta());
-            if timer.just_finished() {
-                let texture_atlas = texture_atlases.get(texture_atlas_handle).unwrap();
-                if maybe_player.is_some() {
-                    sprite.index = (sprite.index + 1) % (texture_atlas.len() / 2);
-                    if move_component.direction.y > 0.0 {
-                        sprite.index += 6;
-                    }
-                } else {
-                    sprite.index = (sprite.index + 1) % texture_atlas.len();
-                }
-            }
+    for (mut animation, mut sprite, move_component, alive) in &mut query {
+        let hurt = alive.health < animation.last_health;
+        animation.last_health = alive.health;
+        let current = library.clip(animation.sheet, animation.clip);
+        let hurt_playing = animation.clip == ClipName::Hurt && animation.frame + 1 < current.len();
+        let direction = move_component.direction;
+        let name = if alive.health <= 0.0 {
+            ClipName::Die
+        } else if hurt || hurt_playing {
+            ClipName::Hurt
+        } else if direction == Vec2::ZERO {
+            ClipName::Idle
+        } else if direction.y > 0.0 {
+            ClipName::RunUp
+        } else {
+            ClipName::RunDown
+        };
+        let clip = library.clip(animation.sheet, name);
+        // Hits during the hurt clip let it play on, sustained contact loops it from the end
+        if name != animation.clip || (hurt && !hurt_playing) {
+            animation.clip = name;
+            animation.frame = 0;
+            animation.timer = Timer::from_seconds(clip.frame_seconds, TimerMode::Repeating);
         } else {
-            sprite.index = 0;
+            animation.timer.tick(time.delta());
+            let steps = animation.timer.times_finished_this_tick() as usize;
+            animation.frame = if clip.looping {
+                (animation.frame + steps) % clip.len()
+            } else {
+                (animation.frame + steps).min(clip.len() - 1)
+            };
+        }
+        sprite.index = clip.first + animation.frame;
+        // Facing is kept while moving straight up or down
+        if direction.x != 0.0 {
+            sprite.flip_x = match clip.flip {
+                FlipRule::Never => false,
+                FlipRule::MovingLeft => direction.x < 0.0,
+                FlipRule::MovingRight => direction.x > 0.0,
+            };
+        } else if matches!(clip.flip, FlipRule::Never) {
+            sprite.flip_x = false;
         }
     }
 }
 
 pub fn setup_in_game(
     mut commands: Commands,
-    stats: Res<StatsRes>,
-    asset_server: Res<AssetServer>,
-    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    party: Res<Party>,
+    gamepads: Res<Gamepads>,
+    assets: Res<GameAssets>,
+    eras: Res<Eras>,
     score: Res<Score>,
-    audio: Res<Audio>,
     mut create_map_event: EventWriter<CreateMapEvent>,
     query_chunks: Query<Entity, With<Chunk>>,
     mut chunk_map_resource: ResMut<ChunksMap>,
+    to_spawn: Res<TotalToSpawn>,
 ) {
-    if score.should_start_music() {
-        let nb_music = score.historic_period_theme();
-        let music = asset_server.load(format!("sounds/in_game_{nb_music}.ogg"));
-        audio.play(music).looped();
+    // A new historic period needs a new map
+    if eras.starts_era(score.get_level_index()) {
         for chunk in query_chunks.iter() {
             commands.entity(chunk).despawn();
         }
         chunk_map_resource.chunks.clear();
     }
     // Camera
-    commands.spawn((Camera2dBundle::default(), InGame));
-    let texture_handle = asset_server.load("images/atlas.png");
-    let texture_atlas =
-        TextureAtlas::from_grid(texture_handle, Vec2::new(64.0, 64.0), 6, 2, None, None);
-    let texture_atlas_handle = texture_atlases.add(texture_atlas);
-    // Use only the subset of sprites in the sheet that make up the run animation
-    // Spawn player
     commands.spawn((
-        PlayerBundle {
-            character: CharacterBundle {
-                move_component: Move {
-                    speed: stats.player_speed,
-                    direction: PLAYER_DIRECTION,
-                },
-                harm: Harm {
-                    damage: stats.player_damage,
-                },
-                alive: Alive {
-                    health: stats.player_health,
+        Camera2dBundle::default(),
+        MainCamera,
+        CameraDirector::default(),
+        InGame,
+    ));
+    // Spawn players, the second one takes the first gamepad or shares the keyboard
+    let mut gamepads = gamepads.iter();
+    let count = party.count();
+    for (i, stats) in party.players.iter().enumerate() {
+        let controls = if i == 0 {
+            Controls::KeyboardMouse
+        } else {
+            gamepads
+                .next()
+                .map_or(Controls::Keyboard, Controls::Gamepad)
+        };
+        let offset = (i as f32 - (count - 1) as f32 / 2.0) * 2.0 * COOP_SPAWN_OFFSET;
+        commands.spawn((
+            PlayerBundle {
+                character: CharacterBundle {
+                    move_component: Move {
+                        speed: stats.player_speed,
+                        direction: PLAYER_DIRECTION,
+                    },
+                    harm: Harm {
+                        damage: stats.player_damage,
+                    },
+                    alive: Alive {
+                        health: stats.player_health,
+                    },
+                    collider: Collider,
+                    in_game: InGame,
                 },
-                collider: Collider,
-                in_game: InGame,
-            },
-            sprite_bundle: SpriteSheetBundle {
-                transform: Transform {
-                    translation: PLAYER_POSITION,
-                    scale: PLAYER_SCALE,
+                sprite_bundle: SpriteSheetBundle {
+                    transform: Transform {
+                        translation: PLAYER_POSITION + Vec3::X * offset,
+                        scale: PLAYER_SCALE,
+                        ..default()
+                    },
+                    sprite: TextureAtlasSprite {
+                        color: stats.player_color,
+                        index: 0,
+                        ..default()
+                    },
+                    texture_atlas: assets.player_atlas.clone(),
                     ..default()
                 },
-                sprite: TextureAtlasSprite {
-                    color: stats.player_color,
-                    index: 0,
-                    ..default()
+                player: Player,
+                weapon: Weapon {
+                    fire_rate: stats.player_fire_rate,
+                    bullet_ttl: stats.player_bullets_ttl,
+                    bullet_speed: stats.player_bullets_speed,
+                    bullets: stats.player_bullets,
+                    is_firing: false,
+                    last_shot: 0.0,
+                },
+                aim: Aim {
+                    direction: PLAYER_AIM,
                 },
-                texture_atlas: texture_atlas_handle,
-                ..default()
-            },
-            player: Player,
-            weapon: Weapon {
-                fire_rate: stats.player_fire_rate,
-                bullet_ttl: stats.player_bullets_ttl,
-                bullet_speed: stats.player_bullets_speed,
-                bullets: stats.player_bullets,
-                is_firing: false,
-            },
-            aim: Aim {
-                direction: PLAYER_AIM,
             },
-        },
-        AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
-    ));
+            Animation::new(AnimationSheet::Player, stats.player_health),
+            controls,
+            PlayerSlot(i),
+        ));
+    }
     commands.spawn((
-        MobSpawnerTimer(Timer::from_seconds(
-            SPEED_SPAWN_BY_LVL[0],
-            TimerMode::Repeating,
-        )),
+        MobSpawnerTimer(Timer::from_seconds(to_spawn.rate, TimerMode::Repeating)),
         InGame,
     ));
     create_map_event.send(CreateMapEvent);
@@ -162,19 +214,22 @@ pub fn clean_in_game(mut commands: Commands, in_game_query: Query<Entity, With<I
 
 pub fn load_chunks(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    assets: Res<GameAssets>,
     mut chunk_map_resource: ResMut<ChunksMap>,
-    query_camera: Query<(&Transform, &OrthographicProjection)>,
+    query_camera: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
     score: Res<Score>,
 ) {
     // Get camera chunk position
-    let (camera_transform, ortho) = query_camera.iter().last().unwrap();
+    let Ok((camera_transform, ortho)) = query_camera.get_single() else {
+        return;
+    };
     let x = camera_transform.translation.x;
     let y = camera_transform.translation.y;
     let chunk_min_x = ((x + ortho.area.min.x) / 256.0).floor() as i32;
     let chunk_max_x = ((x + ortho.area.max.x) / 256.0).floor() as i32;
     let chunk_min_y = ((y + ortho.area.min.y) / 256.0).floor() as i32;
     let chunk_max_y = ((y + ortho.area.max.y) / 256.0).floor() as i32;
+    let era = assets.era_at(score.get_level_index());
     // Check every chunk around the camera
     (chunk_min_x - 2..chunk_max_x + 2).for_each(|i| {
         (chunk_min_y - 2..chunk_max_y + 2).for_each(|j| {
@@ -195,11 +250,11 @@ pub fn load_chunks(
                                 scale: MAP_SCALE,
                                 ..default()
                             },
-                            sprite: Sprite { ..default() },
-                            texture: asset_server.load(format!(
-                                "images/map_chunk_{}.png",
-                                score.historic_period_theme()
-                            )),
+                            sprite: Sprite {
+                                color: era.tint,
+                                ..default()
+                            },
+                            texture: era.chunk_tile(i, j),
                             ..default()
                         },
                         Chunk,
@@ -215,17 +270,20 @@ pub fn load_chunks(
 
 pub fn make_map(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    query_camera: Query<(&Transform, &OrthographicProjection)>,
+    assets: Res<GameAssets>,
+    query_camera: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
     mut create_map_event: EventReader<CreateMapEvent>,
     score: Res<Score>,
 ) {
     if create_map_event.is_empty() {
         return;
     }
+    // Wait for the camera spawned along with the event
+    let Ok((camera_transform, orth)) = query_camera.get_single() else {
+        return;
+    };
     create_map_event.clear();
     // Determine camera chunk position (chunk is 256x256 pixels image)
-    let (camera_transform, orth) = query_camera.iter().last().unwrap();
     let x = camera_transform.translation.x;
     let y = camera_transform.translation.y;
 
@@ -235,13 +293,9 @@ pub fn make_map(
     let n_chunks_to_make_horizontal = (orth.area.max.x / 256.0) as i32 + 2;
     let n_chunks_to_make_vertical = (orth.area.max.y / 256.0) as i32 + 2;
 
-    let texture = asset_server.load(format!(
-        "images/map_chunk_{}.png",
-        score.historic_period_theme()
-    ));
-    let chunks = (-2..n_chunks_to_make_horizontal)
-        .map(move |i| {
-            let texture = texture.clone();
+    let era = assets.era_at(score.get_level_index());
+    let chunks: Vec<_> = (-2..n_chunks_to_make_horizontal)
+        .flat_map(|i| {
             (-2..n_chunks_to_make_vertical).map(move |j| {
                 (
                     SpriteBundle {
@@ -254,113 +308,211 @@ pub fn make_map(
                             scale: PLAYER_SCALE,
                             ..default()
                         },
-                        sprite: Sprite { ..default() },
-                        texture: texture.clone(),
+                        sprite: Sprite {
+                            color: era.tint,
+                            ..default()
+                        },
+                        texture: era.chunk_tile(i, j),
                         ..default()
                     },
                     Chunk,
                 )
             })
         })
-        .flatten();
+        .collect();
     commands.spawn_batch(chunks);
 
     print!("Camera position: ({chunk_x},{chunk_y})");
 }
 
+// Stick position, zeroed inside the dead zone
+fn gamepad_stick(
+    axes: &Axis<GamepadAxis>,
+    gamepad: Gamepad,
+    x: GamepadAxisType,
+    y: GamepadAxisType,
+) -> Vec2 {
+    let stick = Vec2::new(
+        axes.get(GamepadAxis::new(gamepad, x)).unwrap_or(0.0),
+        axes.get(GamepadAxis::new(gamepad, y)).unwrap_or(0.0),
+    );
+    if stick.length() < GAMEPAD_DEADZONE {
+        Vec2::ZERO
+    } else {
+        stick
+    }
+}
+
 //todo, fix the player direction
 pub fn player_aim_update(
     windows: Query<&Window>,
-    mut query: Query<(&Transform, &mut Aim), With<Player>>,
-    query_camera: Query<(&Transform, &OrthographicProjection)>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    mut query: Query<(&Transform, &Move, &Controls, &mut Aim), LivingPlayer>,
+    query_camera: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
 ) {
-    let window = windows.single();
-    let (player_transform, mut player_aim) = query.single_mut();
-    let (transform, projection) = query_camera.iter().last().unwrap();
-    if let Some(position) = window.cursor_position() {
-        player_aim.direction = Vec2::new(
-            (position.x + transform.translation.x + projection.area.min.x)
-                - player_transform.translation.x,
-            (position.y + transform.translation.y + projection.area.min.y)
-                - player_transform.translation.y,
-        )
-        .normalize_or_zero()
+    let Ok((transform, projection)) = query_camera.get_single() else {
+        return;
+    };
+    let cursor = windows
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position());
+    for (player_transform, movable, controls, mut player_aim) in &mut query {
+        let direction = match controls {
+            // The projection scale accounts for the camera zooming out to frame every player
+            Controls::KeyboardMouse => cursor.map(|position| {
+                transform.translation.truncate() + projection.area.min + position * projection.scale
+                    - player_transform.translation.truncate()
+            }),
+            Controls::Keyboard => Some(movable.direction),
+            Controls::Gamepad(gamepad) => {
+                let stick = gamepad_stick(
+                    &gamepad_axes,
+                    *gamepad,
+                    GamepadAxisType::RightStickX,
+                    GamepadAxisType::RightStickY,
+                );
+                Some(if stick == Vec2::ZERO {
+                    movable.direction
+                } else {
+                    stick
+                })
+            }
+        };
+        // Standing still keeps the last aim of players without a mouse
+        if let Some(direction) = direction.and_then(Vec2::try_normalize) {
+            player_aim.direction = direction;
+        }
     }
 }
 
-pub fn camera_position_update(
-    time: Res<Time>,
-    mut query_camera: Query<&mut Transform, With<OrthographicProjection>>,
-    query: Query<&Transform, (With<Player>, Without<OrthographicProjection>)>,
-) {
-    let player_transform = query.single();
-    query_camera.for_each_mut(|mut camera_transform| {
-        let offset =
-            (player_transform.translation).truncate() - camera_transform.translation.truncate();
-        let direction = offset.normalize_or_zero();
-        let magnitude = offset.distance(Vec2::ZERO);
-        let result = direction * magnitude * 5.5 * time.delta_seconds();
-        camera_transform.translation += Vec3::new(result.x, result.y, 0.0);
-    });
+// Fire buttons of every input device a player can use
+#[derive(SystemParam)]
+pub struct FireButtons<'w> {
+    mouse: Res<'w, Input<MouseButton>>,
+    keys: Res<'w, Input<KeyCode>>,
+    gamepad: Res<'w, Input<GamepadButton>>,
+}
+
+impl FireButtons<'_> {
+    fn just_pressed(&self, controls: Controls) -> bool {
+        match controls {
+            Controls::KeyboardMouse => self.mouse.just_pressed(MouseButton::Left),
+            Controls::Keyboard => self.keys.just_pressed(KeyCode::Space),
+            Controls::Gamepad(gamepad) => self.gamepad.just_pressed(GamepadButton::new(
+                gamepad,
+                GamepadButtonType::RightTrigger2,
+            )),
+        }
+    }
+
+    fn just_released(&self, controls: Controls) -> bool {
+        match controls {
+            Controls::KeyboardMouse => self.mouse.just_released(MouseButton::Left),
+            Controls::Keyboard => self.keys.just_released(KeyCode::Space),
+            Controls::Gamepad(gamepad) => self.gamepad.just_released(GamepadButton::new(
+                gamepad,
+                GamepadButtonType::RightTrigger2,
+            )),
+        }
+    }
 }
 
 pub fn mouse_button_input_update(
     time: Res<Time>,
-    mut last_shot: ResMut<LastShot>,
     mut ev_spawn_bullet: EventWriter<SpawnBulletEvent>,
-    buttons: Res<Input<MouseButton>>,
-    mut query: Query<&mut Weapon, With<Player>>,
+    buttons: FireButtons,
+    settings: Res<Settings>,
+    mut query: Query<(Entity, &Controls, &mut Weapon), LivingPlayer>,
 ) {
-    let mut weapon = query.single_mut();
-    if buttons.just_pressed(MouseButton::Left) {
-        let now = time.elapsed_seconds();
-        if now - last_shot.delta_time > weapon.fire_rate {
-            ev_spawn_bullet.send(SpawnBulletEvent);
-            last_shot.delta_time = time.elapsed_seconds();
+    let fire_mode = settings.accessibility.fire_mode;
+    for (entity, controls, mut weapon) in &mut query {
+        if buttons.just_pressed(*controls) {
+            // In toggle mode the second press stops firing
+            if fire_mode == FireMode::Toggle && weapon.is_firing {
+                weapon.is_firing = false;
+                continue;
+            }
+            let now = time.elapsed_seconds();
+            if now - weapon.last_shot > weapon.fire_rate {
+                ev_spawn_bullet.send(SpawnBulletEvent { shooter: entity });
+                weapon.last_shot = now;
+            }
+            weapon.is_firing = true;
+        } else if buttons.just_released(*controls) && fire_mode == FireMode::Hold {
+            weapon.is_firing = false;
         }
-        weapon.is_firing = true;
-    } else if buttons.just_released(MouseButton::Left) {
-        weapon.is_firing = false;
     }
 }
 
+// Enemies chase the nearest player still fighting
 pub fn enemy_direction_update(
     mut query: Query<(&mut Move, &Transform), With<Enemy>>,
-    query_player: Query<&Transform, With<Player>>,
+    query_player: Query<&Transform, LivingPlayer>,
 ) {
-    let player_tranform = query_player.single();
+    let players: Vec<Vec3> = query_player
+        .iter()
+        .map(|transform| transform.translation)
+        .collect();
     query.for_each_mut(|(mut movable, transform)| {
-        movable.direction = (player_tranform.translation - transform.translation)
+        let Some(target) = players.iter().min_by(|a, b| {
+            a.distance_squared(transform.translation)
+                .total_cmp(&b.distance_squared(transform.translation))
+        }) else {
+            return;
+        };
+        movable.direction = (*target - transform.translation)
             .truncate()
             .normalize_or_zero();
     });
 }
 
-pub fn key_input_update(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut query: Query<&mut Move, With<Player>>,
-) {
-    let mut move_player = query.single_mut();
-    move_player.direction = Vec2::ZERO;
+fn key_direction(keyboard_input: &Input<KeyCode>, [left, right, down, up]: [KeyCode; 4]) -> Vec2 {
+    let mut direction = Vec2::ZERO;
 
-    if keyboard_input.pressed(KeyCode::Left) {
-        move_player.direction.x -= 1.0;
+    if keyboard_input.pressed(left) {
+        direction.x -= 1.0;
     }
 
-    if keyboard_input.pressed(KeyCode::Right) {
-        move_player.direction.x += 1.0;
+    if keyboard_input.pressed(right) {
+        direction.x += 1.0;
     }
 
-    if keyboard_input.pressed(KeyCode::Down) {
-        move_player.direction.y -= 1.0;
+    if keyboard_input.pressed(down) {
+        direction.y -= 1.0;
     }
 
-    if keyboard_input.pressed(KeyCode::Up) {
-        move_player.direction.y += 1.0;
+    if keyboard_input.pressed(up) {
+        direction.y += 1.0;
     }
 
-    if let Some(new_value) = move_player.direction.try_normalize() {
-        move_player.direction = new_value;
+    direction.normalize_or_zero()
+}
+
+pub fn key_input_update(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    mut query: Query<(&Controls, &mut Move), LivingPlayer>,
+) {
+    for (controls, mut move_player) in &mut query {
+        move_player.direction = match controls {
+            Controls::KeyboardMouse => key_direction(
+                &keyboard_input,
+                [KeyCode::Left, KeyCode::Right, KeyCode::Down, KeyCode::Up],
+            ),
+            Controls::Keyboard => key_direction(
+                &keyboard_input,
+                [KeyCode::A, KeyCode::D, KeyCode::S, KeyCode::W],
+            ),
+            // Analog, a half pushed stick walks at half speed
+            Controls::Gamepad(gamepad) => gamepad_stick(
+                &gamepad_axes,
+                *gamepad,
+                GamepadAxisType::LeftStickX,
+                GamepadAxisType::LeftStickY,
+            )
+            .clamp_length_max(1.0),
+        };
     }
 }
 
@@ -377,24 +529,32 @@ pub fn transform_update(time: Res<Time>, mut query: Query<(&mut Transform, &Move
 pub fn firing_bullet_emit(
     time: Res<Time>,
     mut ev_spawn_bullet: EventWriter<SpawnBulletEvent>,
-    mut last_shot: ResMut<LastShot>,
-    query: Query<&Weapon, With<Player>>,
+    mut query: Query<(Entity, &mut Weapon), LivingPlayer>,
 ) {
-    let weapon = query.single();
     let now = time.elapsed_seconds();
-    if weapon.is_firing && (now - last_shot.delta_time > weapon.fire_rate) {
-        ev_spawn_bullet.send(SpawnBulletEvent);
-        last_shot.delta_time = now;
+    for (entity, mut weapon) in &mut query {
+        if weapon.is_firing && (now - weapon.last_shot > weapon.fire_rate) {
+            ev_spawn_bullet.send(SpawnBulletEvent { shooter: entity });
+            weapon.last_shot = now;
+        }
     }
 }
 
 pub fn bullet_spawner(
     mut commands: Commands,
     mut ev_spawn_bullet: EventReader<SpawnBulletEvent>,
+    mut particle_events: EventWriter<ParticleEvent>,
     query: Query<(&Transform, &Weapon, &Aim, &Harm), With<Player>>,
 ) {
-    for _ in ev_spawn_bullet.iter() {
-        let (player_transform, weapon, aim, harm) = query.single();
+    for event in ev_spawn_bullet.iter() {
+        let Ok((player_transform, weapon, aim, harm)) = query.get(event.shooter) else {
+            continue;
+        };
+        particle_events.send(ParticleEvent {
+            effect: ParticleEffect::MuzzleFlash,
+            position: player_transform.translation,
+            direction: aim.direction,
+        });
         let angle = aim.direction.angle_between(Vec2::new(1.0, 0.0));
         for i in 0..weapon.bullets {
             let i_f32 = i as f32;
@@ -451,35 +611,104 @@ pub fn manage_mob_spawner_timer(
     }
 }
 
+// One enemy of the given kind, walking towards the target
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    kind: &EnemyKind,
+    texture_atlas: &Handle<TextureAtlas>,
+    position: Vec3,
+    target: Vec3,
+    is_elite: bool,
+    modifiers: &DifficultyModifiers,
+) {
+    let (health_multiplier, speed_multiplier, scale) = if is_elite {
+        (ELITE_HEALTH_MULTIPLIER, ELITE_SPEED_MULTIPLIER, ELITE_SCALE)
+    } else {
+        (1.0, 1.0, MOB_SCALE)
+    };
+    let health = MOB_HEALTH * modifiers.enemy_health * kind.health * health_multiplier;
+    let mut mob = commands.spawn((
+        EnemyBundle {
+            character: CharacterBundle {
+                in_game: InGame,
+                move_component: Move {
+                    speed: MOB_SPEED * modifiers.enemy_speed * kind.speed * speed_multiplier,
+                    direction: (target - position).truncate().normalize_or_zero(),
+                },
+                harm: Harm {
+                    damage: MOB_DAMAGE * modifiers.enemy_damage,
+                },
+                alive: Alive { health },
+                collider: Collider,
+            },
+            sprite_bundle: SpriteSheetBundle {
+                transform: Transform {
+                    translation: position,
+                    scale,
+                    ..default()
+                },
+                sprite: TextureAtlasSprite {
+                    color: MOB_COLOR,
+                    index: 0,
+                    ..default()
+                },
+                texture_atlas: texture_atlas.clone(),
+                ..default()
+            },
+            enemy: Enemy,
+        },
+        Animation::new(AnimationSheet::Mob, health),
+        MaxHealth(health),
+    ));
+    if is_elite {
+        mob.insert(Elite);
+    }
+}
+
+// What the next enemies are drawn from, the roster of the era and the difficulty of the run
+#[derive(SystemParam)]
+pub struct EnemyDraw<'w> {
+    assets: Res<'w, GameAssets>,
+    score: Res<'w, Score>,
+    rng: ResMut<'w, GameRng>,
+}
+
 // Spawn the mob facing towards the player
 pub fn mob_spawner(
     mut commands: Commands,
-    query: Query<&Transform, With<Player>>,
+    query: Query<&Transform, LivingPlayer>,
     mut mob_spawn_event: EventReader<MobSpawnEvent>,
-    asset_server: Res<AssetServer>,
     to_spawn: Res<TotalToSpawn>,
     mut spawned: ResMut<TotalSpawned>,
-    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    mut combat_sound_events: EventWriter<CombatSoundEvent>,
+    mut draw: EnemyDraw,
 ) {
-    let mob_is_robot = thread_rng().gen_range(0..4) == 3;
-    let texture_handle = if mob_is_robot {
-        asset_server.load("images/mob_atlas_1.png")
-    } else {
-        asset_server.load("images/mob_atlas_0.png")
-    };
-    let texture_atlas =
-        TextureAtlas::from_grid(texture_handle, Vec2::new(64.0, 64.0), 5, 1, None, None);
-    let texture_atlas_handle = texture_atlases.add(texture_atlas);
+    let EnemyDraw { assets, score, rng } = &mut draw;
     if mob_spawn_event.is_empty() || to_spawn.amount <= spawned.amount {
         return;
     }
     mob_spawn_event.clear();
+    // Only roll on an actual spawn so a seeded run does not depend on the frame rate
+    let mob_is_robot = rng.gen::<f32>() < to_spawn.robot_ratio;
+    let roster = assets.era_at(score.get_level_index()).roster(mob_is_robot);
+    // Shipped eras have a single kind of each, only rosters with a choice draw one
+    let (kind, texture_atlas) = match roster.len() {
+        1 => &roster[0],
+        len => &roster[rng.gen_range(0..len)],
+    };
 
-    let player = query.single();
+    // Waves gather around one of the players still fighting, a solo run draws nothing more
+    let players: Vec<&Transform> = query.iter().collect();
+    let player = match players.len() {
+        0 => return,
+        1 => players[0],
+        len => players[rng.gen_range(0..len)],
+    };
+    let modifiers = score.difficulty().modifiers();
 
     // Spawn
-    for _ in 0..to_spawn.quantity_per_spawn {
-        let angle = (thread_rng().gen_range(0..3600) as f32) / 10.0 * std::f32::consts::PI / 180.0;
+    for i in 0..to_spawn.quantity_per_spawn {
+        let angle = (rng.gen_range(0..3600) as f32) / 10.0 * std::f32::consts::PI / 180.0;
         let (x, y) = (
             angle.cos() * MOB_SPAWN_RADIUS,
             -angle.sin() * MOB_SPAWN_RADIUS,
@@ -490,66 +719,104 @@ pub fn mob_spawner(
             z: player.translation.z,
         };
         if to_spawn.amount > spawned.amount {
-            commands.spawn((
-                EnemyBundle {
-                    character: CharacterBundle {
-                        in_game: InGame,
-                        move_component: Move {
-                            speed: MOB_SPEED,
-                            direction: (player.translation - mob_spawn_position)
-                                .truncate()
-                                .normalize(),
-                        },
-                        harm: Harm { damage: MOB_DAMAGE },
-                        alive: Alive {
-                            health: if mob_is_robot {
-                                MOB_HEALTH * 2.0
-                            } else {
-                                MOB_HEALTH
-                            },
-                        },
-                        collider: Collider,
-                    },
-                    sprite_bundle: SpriteSheetBundle {
-                        transform: Transform {
-                            translation: mob_spawn_position,
-                            scale: MOB_SCALE,
-                            ..default()
-                        },
-                        sprite: TextureAtlasSprite {
-                            color: MOB_COLOR,
-                            index: 0,
-                            ..default()
-                        },
-                        texture_atlas: texture_atlas_handle.clone(),
-                        ..default()
-                    },
-                    enemy: Enemy,
-                },
-                AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
-            ));
+            // One spawn sound per burst is enough to locate it
+            if i == 0 {
+                combat_sound_events.send(CombatSoundEvent {
+                    sound: CombatSound::Spawn,
+                    position: mob_spawn_position,
+                });
+            }
+            let is_elite = rng.gen::<f32>() < to_spawn.elite_ratio;
+            spawn_enemy(
+                &mut commands,
+                kind,
+                texture_atlas,
+                mob_spawn_position,
+                player.translation,
+                is_elite,
+                &modifiers,
+            );
             spawned.amount += 1;
         }
     }
 }
 
+// Everything a death is seen, heard and felt through
+#[derive(SystemParam)]
+pub struct DeathFeedback<'w> {
+    sounds: EventWriter<'w, CombatSoundEvent>,
+    particles: EventWriter<'w, ParticleEvent>,
+    trauma: EventWriter<'w, CameraTraumaEvent>,
+}
+
+// Anything but players that dies, enemies are counted and felt
+type Mortal<'a> = (
+    Entity,
+    &'a Alive,
+    &'a Transform,
+    Option<&'a Enemy>,
+    Option<&'a Elite>,
+);
+
 pub fn despawn_health(
     mut commands: Commands,
-    mut query: Query<(Entity, &Alive, Option<&Enemy>, Option<&Player>)>,
+    query: Query<Mortal, Without<Player>>,
+    mut query_player: Query<
+        (
+            Entity,
+            &Alive,
+            &mut TextureAtlasSprite,
+            &mut Move,
+            &mut Weapon,
+        ),
+        LivingPlayer,
+    >,
     mut total_killed: ResMut<TotalKilled>,
     mut game_over_event_emitter: EventWriter<GameOverEvent>,
+    mut feedback: DeathFeedback,
+    mut kill_events: EventWriter<EnemyKilledEvent>,
 ) {
-    for (entity, alive, maybe_enemy, maybe_player) in query.iter_mut() {
+    let mut trauma = 0.0;
+    for (entity, alive, transform, maybe_enemy, maybe_elite) in query.iter() {
         if alive.health <= 0.0 {
             if maybe_enemy.is_some() {
                 total_killed.amount += 1;
+                kill_events.send(EnemyKilledEvent {
+                    position: transform.translation,
+                    elite: maybe_elite.is_some(),
+                });
+                trauma += TRAUMA_ENEMY_DEATH;
+                feedback.sounds.send(CombatSoundEvent {
+                    sound: CombatSound::Death,
+                    position: transform.translation,
+                });
+                feedback.particles.send(ParticleEvent {
+                    effect: ParticleEffect::Death,
+                    position: transform.translation,
+                    direction: Vec2::ZERO,
+                });
             }
-            if maybe_player.is_some() {
-                game_over_event_emitter.send(GameOverEvent);
-            } else {
-                commands.entity(entity).despawn();
-            }
+            commands.entity(entity).despawn();
+        }
+    }
+    // Players are downed instead, the run is over once nobody is left fighting
+    let mut living = 0;
+    for (entity, alive, mut sprite, mut movable, mut weapon) in &mut query_player {
+        if alive.health > 0.0 {
+            living += 1;
+            continue;
         }
+        commands.entity(entity).insert(Downed);
+        trauma += TRAUMA_PLAYER_DOWNED;
+        sprite.color.set_a(DOWNED_ALPHA);
+        movable.direction = Vec2::ZERO;
+        weapon.is_firing = false;
+    }
+    if trauma > 0.0 {
+        feedback.trauma.send(CameraTraumaEvent { trauma });
+    }
+    if living == 0 {
+        game_over_event_emitter.send(GameOverEvent);
     }
 }
 
@@ -563,23 +830,38 @@ pub fn wave_is_done_emit(
     }
 }
 
+// Enemy counts of the current wave, and the curve the next waves are sized from
+#[derive(SystemParam)]
+pub struct WaveTotals<'w> {
+    to_spawn: ResMut<'w, TotalToSpawn>,
+    spawned: ResMut<'w, TotalSpawned>,
+    killed: ResMut<'w, TotalKilled>,
+    endless_curve: Res<'w, EndlessCurve>,
+}
+
+impl WaveTotals<'_> {
+    // Nothing spawned nor killed yet, as many enemies to come as the wave calls for
+    pub fn start(&mut self, level: u32, difficulty: Difficulty) {
+        self.killed.amount = 0;
+        self.spawned.amount = 0;
+        self.to_spawn
+            .update_paramter_for_level_id(level, difficulty, &self.endless_curve);
+    }
+}
+
 pub fn change_level(
     mut app_state: ResMut<NextState<AppState>>,
     mut score: ResMut<Score>,
     mut wave_done_event: EventReader<WaveDoneEvent>,
-    mut total_spawned: ResMut<TotalSpawned>,
-    mut total_to_spawn: ResMut<TotalToSpawn>,
-    mut total_killed: ResMut<TotalKilled>,
+    mut totals: WaveTotals,
     mut quey_timer_spawner: Query<&mut MobSpawnerTimer>,
 ) {
     if !wave_done_event.is_empty() {
         wave_done_event.clear();
         score.level_up();
         let mut timer_spawner = quey_timer_spawner.single_mut();
-        timer_spawner.0 = Timer::from_seconds(total_to_spawn.rate, TimerMode::Repeating);
-        total_killed.amount = 0;
-        total_spawned.amount = 0;
-        total_to_spawn.update_paramter_for_level_id(score.get_level_index());
+        timer_spawner.0 = Timer::from_seconds(totals.to_spawn.rate, TimerMode::Repeating);
+        totals.start(score.get_level_index(), score.difficulty());
         app_state.set(AppState::LevelMenu);
     }
 }
@@ -628,59 +910,99 @@ pub fn lerp_color(color: Color, other_color: Color, value: f32) -> Color {
 }
 
 pub fn bullet_hitting_update(
-    mut query_bullets: Query<(&Transform, &Harm, &mut HitCount), (With<Bullet>, Without<Enemy>)>,
+    mut query_bullets: Query<
+        (&Transform, &Move, &Harm, &mut HitCount),
+        (With<Bullet>, Without<Enemy>),
+    >,
     mut query_enemy: Query<
-        (&Transform, &mut Alive, &mut TextureAtlasSprite),
+        (&Transform, &mut Alive, &MaxHealth, &mut TextureAtlasSprite),
         (With<Enemy>, Without<Bullet>),
     >,
-    asset_server: Res<AssetServer>,
-    audio: Res<Audio>,
+    mut combat_sound_events: EventWriter<CombatSoundEvent>,
+    mut particle_events: EventWriter<ParticleEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    settings: Res<Settings>,
 ) {
-    let mut is_hitting = false;
-    query_bullets.for_each_mut(|(bullet_transform, bullet_harm, mut hit_count)| {
-        query_enemy.for_each_mut(|(enemy_transform, mut enemy_alive, mut sprite)| {
-            //collide
-            if hit_count.ttl <= 0 {
-                return;
-            }
-            if let Some(_) = collide(
-                bullet_transform.translation,
-                bullet_transform.scale.truncate(),
-                enemy_transform.translation,
-                enemy_transform.scale.truncate() * 32.0,
-            ) {
-                is_hitting = true;
-                enemy_alive.health -= bullet_harm.damage;
-                sprite.color =
-                    lerp_color(MOB_COLOR_HURT, MOB_COLOR, enemy_alive.health / MOB_HEALTH);
-                hit_count.ttl -= 1;
-            }
-        });
-    });
-    if is_hitting {
-        audio.play(asset_server.load("sounds/hit.ogg"));
-    }
+    let hurt_color = settings.accessibility.palette.hurt_color();
+    query_bullets.for_each_mut(
+        |(bullet_transform, bullet_move, bullet_harm, mut hit_count)| {
+            query_enemy.for_each_mut(
+                |(enemy_transform, mut enemy_alive, max_health, mut sprite)| {
+                    //collide
+                    if hit_count.ttl <= 0 {
+                        return;
+                    }
+                    if let Some(_) = collide(
+                        bullet_transform.translation,
+                        bullet_transform.scale.truncate(),
+                        enemy_transform.translation,
+                        enemy_transform.scale.truncate() * 32.0,
+                    ) {
+                        combat_sound_events.send(CombatSoundEvent {
+                            sound: CombatSound::Hit,
+                            position: enemy_transform.translation,
+                        });
+                        particle_events.send(ParticleEvent {
+                            effect: ParticleEffect::Hit,
+                            position: bullet_transform.translation,
+                            direction: bullet_move.direction,
+                        });
+                        let health = enemy_alive.health;
+                        enemy_alive.health -= bullet_harm.damage;
+                        // Bullets still hit an enemy on its last frame, only its killing blow counts
+                        if health > 0.0 {
+                            damage_events.send(DamageEvent {
+                                position: enemy_transform.translation,
+                                damage: bullet_harm.damage,
+                                killing_blow: enemy_alive.health <= 0.0,
+                            });
+                        }
+                        sprite.color =
+                            lerp_color(hurt_color, MOB_COLOR, enemy_alive.health / max_health.0);
+                        hit_count.ttl -= 1;
+                    }
+                },
+            );
+        },
+    );
 }
 
 pub fn enemy_hitting_update(
-    mut query_player: Query<(&Transform, &mut Alive), (With<Player>, Without<Enemy>)>,
+    mut query_player: Query<
+        (Entity, &Transform, &mut Alive),
+        (With<Player>, Without<Enemy>, Without<Downed>),
+    >,
     query_enemy: Query<(&Transform, &Alive, &Harm), (With<Enemy>, Without<Player>)>,
+    mut trauma_events: EventWriter<CameraTraumaEvent>,
+    mut touched: Local<HashSet<Entity>>,
 ) {
-    let (player_transform, mut player_life) = query_player.single_mut();
-    query_enemy.for_each(|(enemy_transform, enemy_life, enemy_harm)| {
-        //collide
-        if player_life.health <= 0.0 {
-            return;
-        }
-        if enemy_life.health > 0.0 {
-            if let Some(_) = collide(
-                player_transform.translation,
-                player_transform.scale.truncate() * 32.0,
-                enemy_transform.translation,
-                enemy_transform.scale.truncate() * 32.0,
-            ) {
-                player_life.health -= enemy_harm.damage;
+    for (entity, player_transform, mut player_life) in &mut query_player {
+        let health = player_life.health;
+        query_enemy.for_each(|(enemy_transform, enemy_life, enemy_harm)| {
+            //collide
+            if player_life.health <= 0.0 {
+                return;
+            }
+            if enemy_life.health > 0.0 {
+                if let Some(_) = collide(
+                    player_transform.translation,
+                    player_transform.scale.truncate() * 32.0,
+                    enemy_transform.translation,
+                    enemy_transform.scale.truncate() * 32.0,
+                ) {
+                    player_life.health -= enemy_harm.damage;
+                }
             }
+        });
+        // Contact hurts every frame, the camera only shakes when it starts
+        if player_life.health < health {
+            if touched.insert(entity) {
+                trauma_events.send(CameraTraumaEvent {
+                    trauma: TRAUMA_PLAYER_HIT,
+                });
+            }
+        } else {
+            touched.remove(&entity);
         }
-    });
+    }
 }
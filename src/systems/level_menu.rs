@@ -1,27 +1,26 @@
-use std::cmp::max;
-
 use bevy::{
+    ecs::system::SystemParam,
     prelude::{
-        AssetServer, BuildChildren, Button, ButtonBundle, Camera2dBundle, Changed, ChildBuilder,
-        Children, Color, Commands, Entity, NextState, NodeBundle, Query, Res, ResMut, TextBundle,
-        With,
+        AssetServer, BuildChildren, ButtonBundle, Camera2dBundle, ChildBuilder, Color, Commands,
+        Entity, EventReader, Handle, Image, NextState, NodeBundle, Query, Res, ResMut, TextBundle,
+        Vec2, With,
     },
     text::{Text, TextStyle},
     time::Time,
     ui::{
-        AlignItems, AlignSelf, BackgroundColor, FlexDirection, Interaction, JustifyContent,
-        PositionType, Size, Style, UiImage, UiRect, Val,
+        AlignItems, AlignSelf, BackgroundColor, FlexDirection, JustifyContent, PositionType, Size,
+        Style, UiImage, UiRect, Val,
     },
     utils::default,
 };
-use bevy_kira_audio::prelude::*;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
 use crate::{
-    components::{DateText, DebufChoices, LevelMenu, LevelMenuPannel},
-    constants::{BEGIN_DATE, LORE_PARENT_CHOICE},
-    resource::Score,
-    StatsRes,
+    components::{DateText, LevelMenu, UiAction},
+    constants::{BEGIN_DATE, UI_INSET_COLOR, UI_PANEL_COLOR},
+    resource::{Eras, GameAssets, GameRng, Localization, Party, Score, Settings, Traits},
+    scripting::{PlayerView, ScriptCommand, ScriptView, Scripts},
+    systems::ui::{button, label, panel, slide_in_panel, UiClickEvent},
 };
 use crate::{
     components::{Debuff, PlayerColor},
@@ -29,73 +28,147 @@ use crate::{
 };
 
 // UI
-const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
-const HOVER_BUTTON: Color = Color::rgb(0.30, 0.30, 0.30);
 const DATE_COLOR_TEXT: Color = Color::rgb(1.0, 0.0, 0.0);
 const WHITE_TEXT: Color = Color::rgb(0.8, 0.8, 0.8);
-const BACKGROUND_COLOR_UI: Color = Color::rgb(0.65, 0.65, 0.65);
-const PANNEL_SPEED: f32 = 100.0;
 const DATE_SPEED: f32 = 30.0;
+
+// A parent the player can pick, with the flaws it passes on
+struct ParentOffer {
+    debuf: Debuff,
+    color: Color,
+    flaws: String,
+}
+
+impl ParentOffer {
+    fn new(debuf: Debuff, color: Color, localization: &Localization, traits: &Traits) -> Self {
+        let flaws = debuf
+            .get_defaults()
+            .iter()
+            .map(|&flaw| localization.format(flaw.key(), &[("percent", &traits.penalty(flaw))]))
+            .collect::<Vec<String>>()
+            .join("\n");
+        Self {
+            debuf,
+            color,
+            flaws,
+        }
+    }
+}
+
+#[derive(SystemParam)]
+pub struct ParentDraw<'w> {
+    rng: ResMut<'w, GameRng>,
+    settings: Res<'w, Settings>,
+    traits: Res<'w, Traits>,
+}
+
+impl ParentDraw<'_> {
+    // Dad then mom
+    fn offers(&mut self, localization: &Localization) -> [ParentOffer; 2] {
+        let (debuf_mom, debuf_dad) = Debuff::get_parent_random(&mut **self.rng);
+        let (color_dad, color_mom) = (
+            random_parent_color(&mut self.rng),
+            random_parent_color(&mut self.rng),
+        );
+        // The random tints are still drawn so a seeded run offers the same parents with any palette
+        let (color_dad, color_mom) = self
+            .settings
+            .accessibility
+            .palette
+            .parent_colors()
+            .unwrap_or((color_dad, color_mom));
+        [
+            ParentOffer::new(debuf_dad, color_dad, localization, &self.traits),
+            ParentOffer::new(debuf_mom, color_mom, localization, &self.traits),
+        ]
+    }
+}
+
+// The party with how hard the flaws it inherits weaken it
+#[derive(SystemParam)]
+pub struct Heredity<'w> {
+    pub party: ResMut<'w, Party>,
+    traits: Res<'w, Traits>,
+    scripts: ResMut<'w, Scripts>,
+    score: Res<'w, Score>,
+}
+
+impl Heredity<'_> {
+    pub fn inherit(&mut self, player: usize, parent: &Debuff, color: Color) {
+        self.party.inherit(player, parent, color, &self.traits);
+        let active = self.scripts.for_flaws(parent.get_defaults());
+        if active.is_empty() {
+            return;
+        }
+        let view = ScriptView {
+            wave: self.score.get_level_index() + 1,
+            year: self.score.get_date(),
+            players: self
+                .party
+                .players
+                .iter()
+                .map(|stats| PlayerView::new(Vec2::ZERO, true, stats))
+                .collect(),
+        };
+        // Nothing is on the field between waves, only stat changes apply
+        for command in self
+            .scripts
+            .call(&active, view, "on_inherit", (player as i64,))
+        {
+            if let ScriptCommand::SetStat {
+                player,
+                stat,
+                value,
+            } = command
+            {
+                stat.set(&mut self.party.players[player], value);
+            }
+        }
+    }
+}
+
 fn heredity_button_layout(
     asset_server: &Res<AssetServer>,
+    localization: &Localization,
     parent: &mut ChildBuilder,
-    debuf: Debuff,
-    color: Color,
+    offer: ParentOffer,
 ) {
-    parent
-        .spawn((
-            ButtonBundle {
-                style: Style {
-                    size: Size::new(Val::Percent(90.0), Val::Px(100.0)),
-                    // horizontally center child text
-                    justify_content: JustifyContent::Center,
-                    // vertically center child text
-                    align_items: AlignItems::Center,
-                    flex_direction: FlexDirection::Column,
-                    ..default()
-                },
-                background_color: NORMAL_BUTTON.into(),
-                ..default()
-            },
+    button(
+        parent,
+        LevelMenu,
+        UiAction::PickParent,
+        Style {
+            size: Size::new(Val::Percent(90.0), Val::Px(100.0)),
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+    )
+    .insert((PlayerColor(offer.color), offer.debuf))
+    .with_children(|parent| {
+        label(
+            parent,
+            asset_server,
             LevelMenu,
-            PlayerColor(color),
-            debuf.clone(),
-        ))
-        .with_children(|parent| {
-            parent.spawn((
-                TextBundle::from_section(
-                    format!("Flaws :\n"),
-                    TextStyle {
-                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                        font_size: 20.0,
-                        color: Color::rgb(0.9, 0.8, 0.3),
-                    },
-                ),
-                LevelMenu,
-            ));
-        })
-        .with_children(|parent| {
-            parent.spawn((
-                TextBundle::from_section(
-                    format!("{debuf}"),
-                    TextStyle {
-                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                        font_size: 20.0,
-                        color: Color::rgb(0.9, 0.3, 0.3),
-                    },
-                ),
-                LevelMenu,
-            ));
-        });
+            &format!("{}\n", localization.get("level.flaws")),
+            20.0,
+            Color::rgb(0.9, 0.8, 0.3),
+        );
+        label(
+            parent,
+            asset_server,
+            LevelMenu,
+            &offer.flaws,
+            20.0,
+            Color::rgb(0.9, 0.3, 0.3),
+        );
+    });
 }
 
-fn heredity_sprite_layout(asset_server: &Res<AssetServer>, parent: &mut ChildBuilder) -> Color {
-    let color = Color::rgb(
-        thread_rng().gen_range(0.0..1.0),
-        thread_rng().gen_range(0.0..1.0),
-        thread_rng().gen_range(0.0..1.0),
-    );
-
+fn heredity_sprite_layout(
+    sprite: &Handle<Image>,
+    parent: &mut ChildBuilder,
+    color: Color,
+) -> Color {
     parent.spawn((
         ButtonBundle {
             style: Style {
@@ -106,7 +179,7 @@ fn heredity_sprite_layout(asset_server: &Res<AssetServer>, parent: &mut ChildBui
                 align_items: AlignItems::Center,
                 ..default()
             },
-            image: UiImage::new(asset_server.load("images/sprite.png")),
+            image: UiImage::new(sprite.clone()),
             background_color: BackgroundColor(color.clone()),
             ..default()
         },
@@ -117,63 +190,63 @@ fn heredity_sprite_layout(asset_server: &Res<AssetServer>, parent: &mut ChildBui
 
 fn heredity_layout(
     asset_server: &Res<AssetServer>,
+    sprite: &Handle<Image>,
+    localization: &Localization,
     parent: &mut ChildBuilder,
-    debuf: Debuff,
+    offer: ParentOffer,
     parent_name: &str,
 ) {
-    parent
-        .spawn((
-            NodeBundle {
-                style: Style {
-                    size: Size::new(Val::Percent(35.0), Val::Percent(100.0)),
-                    align_items: AlignItems::Center,
-                    flex_direction: FlexDirection::Column,
-                    justify_content: JustifyContent::SpaceAround,
-                    ..default()
-                },
-                background_color: BACKGROUND_COLOR_UI.into(),
+    panel(
+        parent,
+        LevelMenu,
+        Style {
+            size: Size::new(Val::Percent(35.0), Val::Percent(100.0)),
+            align_items: AlignItems::Center,
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::SpaceAround,
+            ..default()
+        },
+        UI_INSET_COLOR,
+    )
+    .with_children(|parent| {
+        panel(
+            parent,
+            LevelMenu,
+            Style {
+                size: Size::new(Val::Percent(95.0), Val::Percent(10.0)),
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::SpaceAround,
                 ..default()
             },
-            LevelMenu,
-        ))
-        .with_children(|parent| {
-            parent
-                .spawn((
-                    NodeBundle {
-                        style: Style {
-                            size: Size::new(Val::Percent(95.0), Val::Percent(10.0)),
-                            align_items: AlignItems::Center,
-                            flex_direction: FlexDirection::Column,
-                            justify_content: JustifyContent::SpaceAround,
-                            ..default()
-                        },
-                        background_color: NORMAL_BUTTON.into(),
-                        ..default()
-                    },
-                    LevelMenu,
-                ))
-                .with_children(|background_title| {
-                    background_title.spawn((
-                        TextBundle::from_section(
-                            format!("{parent_name}"),
-                            TextStyle {
-                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                                font_size: 20.0,
-                                color: WHITE_TEXT,
-                            },
-                        ),
-                        LevelMenu,
-                    ));
-                });
-        })
-        .with_children(|parent| {
-            let color = heredity_sprite_layout(&asset_server, parent);
-            heredity_button_layout(&asset_server, parent, debuf, color);
+            UI_PANEL_COLOR,
+        )
+        .with_children(|background_title| {
+            label(
+                background_title,
+                asset_server,
+                LevelMenu,
+                localization.get(parent_name),
+                20.0,
+                WHITE_TEXT,
+            );
         });
+        heredity_sprite_layout(sprite, parent, offer.color);
+        heredity_button_layout(asset_server, localization, parent, offer);
+    });
 }
 
-pub fn setup_level_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub fn setup_level_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    assets: Res<GameAssets>,
+    mut draw: ParentDraw,
+    localization: Res<Localization>,
+    party: Res<Party>,
+) {
     commands.spawn((Camera2dBundle::default(), LevelMenu));
+    // Co-op players choose one after the other
+    let chooser = (party.count() > 1).then_some(party.picking);
     commands
         .spawn((
             NodeBundle {
@@ -188,77 +261,102 @@ pub fn setup_level_menu(mut commands: Commands, asset_server: Res<AssetServer>)
             LevelMenu,
         ))
         .with_children(|parent| {
-            parent
-                .spawn((
-                    NodeBundle {
-                        style: Style {
-                            size: Size::new(Val::Percent(70.0), Val::Percent(70.0)),
-                            align_items: AlignItems::Center,
-                            justify_content: JustifyContent::SpaceAround,
-                            position: UiRect {
-                                top: Val::Percent(-83.0),
+            slide_in_panel(
+                parent,
+                LevelMenu,
+                Style {
+                    size: Size::new(Val::Percent(70.0), Val::Percent(70.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::SpaceAround,
+                    position: UiRect {
+                        top: Val::Percent(-83.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                UI_PANEL_COLOR,
+            )
+            .with_children(|parent| {
+                parent
+                    .spawn((
+                        NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Auto, Val::Auto),
+                                align_items: AlignItems::Center,
+                                justify_content: JustifyContent::SpaceAround,
+                                position_type: PositionType::Absolute,
                                 ..default()
                             },
                             ..default()
                         },
-                        background_color: NORMAL_BUTTON.into(),
-                        ..default()
-                    },
-                    LevelMenu,
-                    LevelMenuPannel,
-                ))
-                .with_children(|parent| {
-                    parent
-                        .spawn((
-                            NodeBundle {
-                                style: Style {
-                                    size: Size::new(Val::Auto, Val::Auto),
-                                    align_items: AlignItems::Center,
-                                    justify_content: JustifyContent::SpaceAround,
-                                    position_type: PositionType::Absolute,
-                                    ..default()
+                        LevelMenu,
+                    ))
+                    .with_children(|date_place| {
+                        date_place.spawn((
+                            TextBundle::from_section(
+                                format!("{}", BEGIN_DATE),
+                                TextStyle {
+                                    font: assets.font.clone(),
+                                    font_size: 30.0,
+                                    color: DATE_COLOR_TEXT,
                                 },
-                                ..default()
-                            },
+                            ),
                             LevelMenu,
-                        ))
-                        .with_children(|date_place| {
-                            date_place.spawn((
-                                TextBundle::from_section(
-                                    format!("{}", BEGIN_DATE),
-                                    TextStyle {
-                                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                                        font_size: 30.0,
-                                        color: DATE_COLOR_TEXT,
-                                    },
-                                ),
-                                LevelMenu,
-                                DateText,
-                            ));
-                        });
-                    parent
-                        .spawn((
-                            NodeBundle {
-                                style: Style {
-                                    size: Size::new(Val::Percent(90.0), Val::Percent(90.0)),
-                                    align_items: AlignItems::Center,
-                                    justify_content: JustifyContent::SpaceAround,
-                                    flex_direction: FlexDirection::Column,
-                                    ..default()
-                                },
+                            DateText,
+                        ));
+                    });
+                parent
+                    .spawn((
+                        NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(90.0), Val::Percent(90.0)),
+                                align_items: AlignItems::Center,
+                                justify_content: JustifyContent::SpaceAround,
+                                flex_direction: FlexDirection::Column,
                                 ..default()
                             },
-                            LevelMenu,
-                        ))
-                        .with_children(|parent| {
-                            content_layout(&asset_server, parent);
-                        });
-                });
+                            ..default()
+                        },
+                        LevelMenu,
+                    ))
+                    .with_children(|parent| {
+                        content_layout(
+                            &asset_server,
+                            &assets,
+                            &localization,
+                            parent,
+                            draw.offers(&localization),
+                            chooser,
+                        );
+                    });
+            });
         });
 }
 
-pub fn content_layout(asset_server: &Res<AssetServer>, parent: &mut ChildBuilder) {
-    let (debuf_mom, debuf_dad) = Debuff::get_parent_random();
+fn random_parent_color(rng: &mut GameRng) -> Color {
+    Color::rgb(
+        rng.gen_range(0.0..1.0),
+        rng.gen_range(0.0..1.0),
+        rng.gen_range(0.0..1.0),
+    )
+}
+
+fn content_layout(
+    asset_server: &Res<AssetServer>,
+    assets: &GameAssets,
+    localization: &Localization,
+    parent: &mut ChildBuilder,
+    [dad, mom]: [ParentOffer; 2],
+    chooser: Option<usize>,
+) {
+    let lore = localization.get("level.choose_parent");
+    let lore = match chooser {
+        Some(player) => format!(
+            "{}\n{lore}",
+            localization.format("level.player_choice", &[("player", &(player + 1))])
+        ),
+        None => lore.to_string(),
+    };
     parent
         .spawn((
             NodeBundle {
@@ -276,9 +374,9 @@ pub fn content_layout(asset_server: &Res<AssetServer>, parent: &mut ChildBuilder
         .with_children(|parent| {
             parent.spawn((
                 TextBundle::from_section(
-                    format!("{LORE_PARENT_CHOICE}"),
+                    lore,
                     TextStyle {
-                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font: assets.font.clone(),
                         font_size: 20.0,
                         color: WHITE_TEXT,
                     },
@@ -300,8 +398,22 @@ pub fn content_layout(asset_server: &Res<AssetServer>, parent: &mut ChildBuilder
             LevelMenu,
         ))
         .with_children(|parent| {
-            heredity_layout(&asset_server, parent, debuf_dad, "Dad");
-            heredity_layout(&asset_server, parent, debuf_mom, "Mom");
+            heredity_layout(
+                asset_server,
+                &assets.parent_sprite,
+                localization,
+                parent,
+                dad,
+                "level.dad",
+            );
+            heredity_layout(
+                asset_server,
+                &assets.parent_sprite,
+                localization,
+                parent,
+                mom,
+                "level.mom",
+            );
         });
     parent.spawn((
         NodeBundle {
@@ -318,29 +430,52 @@ pub fn content_layout(asset_server: &Res<AssetServer>, parent: &mut ChildBuilder
     ));
 }
 
-pub fn clean_level_menu(
+// Name the historic period the wave about to start takes the run back to, the story tells its lore
+pub fn setup_era_intro(
     mut commands: Commands,
-    main_menu_query: Query<Entity, With<LevelMenu>>,
+    asset_server: Res<AssetServer>,
+    eras: Res<Eras>,
     score: Res<Score>,
-    audio: Res<Audio>,
+    localization: Res<Localization>,
 ) {
-    for entity in main_menu_query.iter() {
-        commands.entity(entity).despawn();
-    }
-    if score.should_start_music() {
-        audio.stop();
+    let level = score.get_level_index();
+    if level == 0 || !eras.starts_era(level) {
+        return;
     }
+    let era = eras.at(level);
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Auto),
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(10.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            LevelMenu,
+        ))
+        .with_children(|parent| {
+            label(
+                parent,
+                &asset_server,
+                LevelMenu,
+                localization.get(&era.name),
+                30.0,
+                DATE_COLOR_TEXT,
+            );
+        });
 }
 
-pub fn down_pannel(time: Res<Time>, mut query_panel: Query<&mut Style, With<LevelMenuPannel>>) {
-    let mut panel = query_panel.single_mut();
-    if let Val::Percent(y) = panel.position.top {
-        let new_value = y + PANNEL_SPEED * time.delta_seconds();
-        if new_value > 0.0 {
-            panel.position.top = Val::Undefined;
-        } else {
-            panel.position.top = Val::Percent(new_value);
-        }
+pub fn clean_level_menu(mut commands: Commands, main_menu_query: Query<Entity, With<LevelMenu>>) {
+    for entity in main_menu_query.iter() {
+        commands.entity(entity).despawn();
     }
 }
 
@@ -355,46 +490,27 @@ pub fn decrement_date(
 }
 
 pub fn heredity_button(
+    mut click_event: EventReader<UiClickEvent>,
     mut app_state: ResMut<NextState<AppState>>,
-    mut stats: ResMut<StatsRes>,
-    mut interaction_query: Query<
-        (
-            &Interaction,
-            &mut BackgroundColor,
-            &Children,
-            &Debuff,
-            &PlayerColor,
-        ),
-        (Changed<Interaction>, With<Button>),
-    >,
+    mut heredity: Heredity,
+    query_parent: Query<(&Debuff, &PlayerColor)>,
 ) {
-    for (interaction, mut button_color, _, debuf, color) in &mut interaction_query {
-        match *interaction {
-            Interaction::Clicked => {
-                debuf
-                    .get_defaults()
-                    .iter()
-                    .for_each(|debuff_choice| match debuff_choice {
-                        DebufChoices::Speed => stats.player_speed *= 0.8,
-                        DebufChoices::Bullets => {
-                            stats.player_bullets = max(stats.player_bullets / 2, 1)
-                        }
-                        DebufChoices::BulletsTtl => {
-                            stats.player_bullets_ttl = max(stats.player_bullets_ttl / 2, 1)
-                        }
-                        DebufChoices::Damage => stats.player_damage *= 0.7,
-                        DebufChoices::BulletsSpeed => {
-                            stats.player_bullets_speed *= 0.6;
-                            stats.player_decay *= 0.6;
-                        }
-                        DebufChoices::FireRate => stats.player_fire_rate *= 1.3,
-                    });
-                stats.player_color = color.0;
+    for event in click_event.iter() {
+        let UiAction::PickParent = event.action else {
+            continue;
+        };
+        if let Ok((debuf, color)) = query_parent.get(event.entity) {
+            let picking = heredity.party.picking;
+            heredity.inherit(picking, debuf, color.0);
+            heredity.party.picking += 1;
+            // Entering the menu again rolls new parents for the next player
+            if heredity.party.picking < heredity.party.count() {
+                app_state.set(AppState::LevelMenu);
+            } else {
+                heredity.party.picking = 0;
                 app_state.set(AppState::InGame);
-                *button_color = NORMAL_BUTTON.into()
             }
-            Interaction::Hovered => *button_color = HOVER_BUTTON.into(),
-            Interaction::None => *button_color = NORMAL_BUTTON.into(),
+            return;
         }
     }
 }
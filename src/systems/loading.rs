@@ -0,0 +1,171 @@
+use bevy::{
+    asset::{AssetServer, LoadState},
+    ecs::system::SystemParam,
+    prelude::{
+        warn, BuildChildren, Camera2dBundle, Commands, Entity, Local, NextState, NodeBundle,
+        Parent, Query, Res, ResMut, With,
+    },
+    text::Text,
+    ui::{AlignItems, FlexDirection, JustifyContent, Size, Style, Val},
+    utils::default,
+};
+
+use crate::{
+    components::{LoadingBar, LoadingScreen, LoadingText, LocalizedText, UiAction},
+    constants::{UI_BUTTON_FOCUS_COLOR, UI_INSET_COLOR, UI_TEXT_COLOR},
+    resource::{GameAssets, Localization},
+    systems::ui::{label, panel, text_button},
+    AppState,
+};
+
+// Share of the registered files done loading, and the path of every file that failed to
+pub fn load_progress(asset_server: &AssetServer, assets: &GameAssets) -> (f32, Vec<String>) {
+    let files = assets.files();
+    let mut done = 0;
+    let mut missing = Vec::new();
+    for handle in files {
+        match asset_server.get_load_state(handle) {
+            LoadState::Loaded => done += 1,
+            LoadState::Failed => {
+                done += 1;
+                missing.push(
+                    asset_server
+                        .get_handle_path(handle)
+                        .map_or_else(|| "?".to_string(), |path| path.path().display().to_string()),
+                );
+            }
+            _ => {}
+        }
+    }
+    (done as f32 / files.len().max(1) as f32, missing)
+}
+
+pub fn setup_loading(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    localization: Res<Localization>,
+) {
+    commands.spawn((Camera2dBundle::default(), LoadingScreen));
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    gap: Size::new(Val::Px(0.0), Val::Px(20.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            LoadingScreen,
+        ))
+        .with_children(|parent| {
+            label(
+                parent,
+                &asset_server,
+                LoadingScreen,
+                localization.get("loading.title"),
+                40.0,
+                UI_TEXT_COLOR,
+            )
+            .insert(LocalizedText("loading.title"));
+            panel(
+                parent,
+                LoadingScreen,
+                Style {
+                    size: Size::new(Val::Px(400.0), Val::Px(24.0)),
+                    ..default()
+                },
+                UI_INSET_COLOR,
+            )
+            .with_children(|parent| {
+                panel(
+                    parent,
+                    LoadingScreen,
+                    Style {
+                        size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                        ..default()
+                    },
+                    UI_BUTTON_FOCUS_COLOR,
+                )
+                .insert(LoadingBar);
+            });
+            label(
+                parent,
+                &asset_server,
+                LoadingScreen,
+                "",
+                20.0,
+                UI_TEXT_COLOR,
+            )
+            .insert(LoadingText);
+        });
+}
+
+#[derive(SystemParam)]
+pub struct LoadingWidgets<'w, 's> {
+    commands: Commands<'w, 's>,
+    asset_server: Res<'w, AssetServer>,
+    query_bar: Query<'w, 's, &'static mut Style, With<LoadingBar>>,
+    query_text: Query<'w, 's, (&'static Parent, &'static mut Text), With<LoadingText>>,
+}
+
+// Moves on to the main menu once every file is there, missing ones are listed on the screen and
+// wait for the player to go on without them
+pub fn loading_update(
+    assets: Res<GameAssets>,
+    localization: Res<Localization>,
+    mut app_state: ResMut<NextState<AppState>>,
+    mut reported: Local<bool>,
+    mut widgets: LoadingWidgets,
+) {
+    if *reported {
+        return;
+    }
+    let (progress, missing) = load_progress(&widgets.asset_server, &assets);
+    for mut style in &mut widgets.query_bar {
+        style.size.width = Val::Percent(progress * 100.0);
+    }
+    if progress < 1.0 {
+        return;
+    }
+    if missing.is_empty() {
+        app_state.set(AppState::MainMenu);
+        return;
+    }
+    *reported = true;
+    for path in &missing {
+        warn!("Missing asset: {path}");
+    }
+    let Ok((screen, mut text)) = widgets.query_text.get_single_mut() else {
+        return;
+    };
+    text.sections[0].value = format!(
+        "{}\n{}",
+        localization.get("loading.missing"),
+        missing.join("\n")
+    );
+    let asset_server = widgets.asset_server;
+    widgets
+        .commands
+        .entity(screen.get())
+        .with_children(|parent| {
+            text_button(
+                parent,
+                &asset_server,
+                LoadingScreen,
+                UiAction::BackToMainMenu,
+                Size::new(Val::Px(300.0), Val::Px(50.0)),
+                localization.get("loading.continue"),
+                LocalizedText("loading.continue"),
+            );
+        });
+}
+
+pub fn clean_loading(mut commands: Commands, query: Query<Entity, With<LoadingScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
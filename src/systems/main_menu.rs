@@ -1,27 +1,39 @@
 use bevy::{
     prelude::{
-        AssetServer, BuildChildren, Button, ButtonBundle, Camera2dBundle, Changed, Children, Color,
-        Commands, Entity, ImageBundle, NextState, NodeBundle, Query, Res, ResMut, TextBundle, With,
-    },
-    text::TextStyle,
-    ui::{
-        AlignItems, BackgroundColor, Interaction, JustifyContent, PositionType, Size, Style,
-        UiImage, Val,
+        AssetServer, BuildChildren, Camera2dBundle, Commands, Entity, EventReader, ImageBundle,
+        NextState, NodeBundle, Query, Res, ResMut, With,
     },
+    text::Text,
+    ui::{AlignItems, FlexDirection, JustifyContent, PositionType, Size, Style, UiImage, Val},
     utils::default,
 };
-use bevy_kira_audio::prelude::*;
 
-use crate::components::MainMenu;
-use crate::AppState;
+use crate::components::{DifficultyText, LocalizedText, MainMenu, PlayersText, UiAction};
+use crate::{
+    resource::{EndlessCurve, GameAssets, Localization, Party, Score, TotalToSpawn},
+    systems::ui::{text_button, UiClickEvent},
+    AppState, Difficulty,
+};
 
-// UI
-const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+fn difficulty_text(localization: &Localization, difficulty: Difficulty) -> String {
+    localization.format(
+        "menu.difficulty",
+        &[("difficulty", &localization.get(difficulty.key()))],
+    )
+}
 
-pub fn setup_main_menu(mut commands: Commands, asset_server: Res<AssetServer>, audio: Res<Audio>) {
-    let music = asset_server.load("sounds/theme.ogg");
-    audio.play(music).looped();
+fn players_text(localization: &Localization, count: usize) -> String {
+    localization.format("menu.players", &[("count", &count)])
+}
 
+pub fn setup_main_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    assets: Res<GameAssets>,
+    score: Res<Score>,
+    party: Res<Party>,
+    localization: Res<Localization>,
+) {
     commands.spawn((Camera2dBundle::default(), MainMenu));
     commands.spawn((
         ImageBundle {
@@ -32,7 +44,7 @@ pub fn setup_main_menu(mut commands: Commands, asset_server: Res<AssetServer>, a
                 position_type: PositionType::Absolute,
                 ..default()
             },
-            image: UiImage::new(asset_server.load("images/title_screen.png")),
+            image: UiImage::new(assets.title_screen.clone()),
             ..default()
         },
         MainMenu,
@@ -44,6 +56,8 @@ pub fn setup_main_menu(mut commands: Commands, asset_server: Res<AssetServer>, a
                     size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
                     align_items: AlignItems::Center,
                     justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    gap: Size::new(Val::Px(0.0), Val::Px(20.0)),
                     ..default()
                 },
                 ..default()
@@ -60,40 +74,75 @@ pub fn setup_main_menu(mut commands: Commands, asset_server: Res<AssetServer>, a
                         position_type: PositionType::Absolute,
                         ..default()
                     },
-                    image: UiImage::new(asset_server.load("images/title_screen.png")),
+                    image: UiImage::new(assets.title_screen.clone()),
                     ..default()
                 },
                 MainMenu,
             ));
-            parent
-                .spawn((
-                    ButtonBundle {
-                        style: Style {
-                            size: Size::new(Val::Px(150.0), Val::Px(65.0)),
-                            // horizontally center child text
-                            justify_content: JustifyContent::Center,
-                            // vertically center child text
-                            align_items: AlignItems::Center,
-                            ..default()
-                        },
-                        background_color: NORMAL_BUTTON.into(),
-                        ..default()
-                    },
-                    MainMenu,
-                ))
-                .with_children(|parent| {
-                    parent.spawn((
-                        TextBundle::from_section(
-                            "Start",
-                            TextStyle {
-                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                                font_size: 40.0,
-                                color: Color::rgb(0.9, 0.9, 0.9),
-                            },
-                        ),
-                        MainMenu,
-                    ));
-                });
+            let button_size = Size::new(Val::Px(300.0), Val::Px(50.0));
+            text_button(
+                parent,
+                &asset_server,
+                MainMenu,
+                UiAction::Start,
+                button_size,
+                localization.get("menu.start"),
+                LocalizedText("menu.start"),
+            );
+            text_button(
+                parent,
+                &asset_server,
+                MainMenu,
+                UiAction::StartDaily,
+                button_size,
+                localization.get("menu.daily"),
+                LocalizedText("menu.daily"),
+            );
+            text_button(
+                parent,
+                &asset_server,
+                MainMenu,
+                UiAction::CycleDifficulty,
+                button_size,
+                &difficulty_text(&localization, score.difficulty()),
+                DifficultyText,
+            );
+            text_button(
+                parent,
+                &asset_server,
+                MainMenu,
+                UiAction::CyclePlayers,
+                button_size,
+                &players_text(&localization, party.count()),
+                PlayersText,
+            );
+            text_button(
+                parent,
+                &asset_server,
+                MainMenu,
+                UiAction::OpenSettings,
+                button_size,
+                localization.get("menu.settings"),
+                LocalizedText("menu.settings"),
+            );
+            text_button(
+                parent,
+                &asset_server,
+                MainMenu,
+                UiAction::OpenMods,
+                button_size,
+                localization.get("menu.mods"),
+                LocalizedText("menu.mods"),
+            );
+            text_button(
+                parent,
+                &asset_server,
+                MainMenu,
+                UiAction::OpenAchievements,
+                button_size,
+                localization.get("menu.achievements"),
+                LocalizedText("menu.achievements"),
+            );
         });
 }
 
@@ -103,19 +152,50 @@ pub fn clean_main_menu(mut commands: Commands, main_menu_query: Query<Entity, Wi
     }
 }
 
-pub fn start_button(
+pub fn main_menu_buttons(
+    mut click_event: EventReader<UiClickEvent>,
     mut app_state: ResMut<NextState<AppState>>,
-    mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor, &Children),
-        (Changed<Interaction>, With<Button>),
-    >,
+    mut score: ResMut<Score>,
+    mut total_to_spawn: ResMut<TotalToSpawn>,
+    endless_curve: Res<EndlessCurve>,
+    mut query_text: Query<&mut Text, With<DifficultyText>>,
+    localization: Res<Localization>,
 ) {
-    for (interaction, _, _) in &mut interaction_query {
-        match *interaction {
-            Interaction::Clicked => {
-                app_state.set(AppState::PreStartMenu);
+    for event in click_event.iter() {
+        match event.action {
+            UiAction::Start => app_state.set(AppState::PreStartMenu),
+            // Cycle through the difficulty presets, the wave parameters follow the selected one
+            UiAction::CycleDifficulty => {
+                let difficulty = score.difficulty().next();
+                score.set_difficulty(difficulty);
+                total_to_spawn.update_paramter_for_level_id(
+                    score.get_level_index(),
+                    difficulty,
+                    &endless_curve,
+                );
+                let mut text = query_text.single_mut();
+                text.sections[0].value = difficulty_text(&localization, difficulty);
             }
+            UiAction::OpenSettings => app_state.set(AppState::SettingsMenu),
+            UiAction::OpenMods => app_state.set(AppState::ModsMenu),
+            UiAction::OpenAchievements => app_state.set(AppState::AchievementsMenu),
             _ => {}
         }
     }
 }
+
+// Switch between a solo run and local co-op with a second player
+pub fn players_button(
+    mut click_event: EventReader<UiClickEvent>,
+    mut party: ResMut<Party>,
+    mut query_text: Query<&mut Text, With<PlayersText>>,
+    localization: Res<Localization>,
+) {
+    for event in click_event.iter() {
+        if let UiAction::CyclePlayers = event.action {
+            *party = Party::new(party.count() % 2 + 1);
+            let mut text = query_text.single_mut();
+            text.sections[0].value = players_text(&localization, party.count());
+        }
+    }
+}
@@ -1,5 +1,22 @@
+pub mod achievements;
+pub mod achievements_menu;
+pub mod attract_mode;
+pub mod bot;
+pub mod camera;
+pub mod combat_audio;
+pub mod combat_text;
+pub mod daily;
+pub mod dialogue;
 pub mod in_game;
 pub mod level_menu;
+pub mod loading;
 pub mod main_menu;
+pub mod mods_menu;
+pub mod music;
+pub mod particles;
 pub mod prestart_menu;
+pub mod radar;
 pub mod retry_menu;
+pub mod scripting;
+pub mod settings_menu;
+pub mod ui;
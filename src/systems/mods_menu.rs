@@ -0,0 +1,256 @@
+use bevy::{
+    prelude::{
+        AssetServer, BuildChildren, Camera2dBundle, ChildBuilder, Color, Commands, Entity,
+        EventReader, NodeBundle, Or, Query, Res, ResMut, With,
+    },
+    text::Text,
+    ui::{AlignItems, FlexDirection, JustifyContent, Size, Style, Val},
+    utils::default,
+};
+use bevy_kira_audio::prelude::*;
+
+use crate::{
+    components::{LocalizedText, ModToggleText, ModsMenu, ModsRestartText, UiAction, UiBackAction},
+    constants::{MODS_FOLDER, UI_PANEL_COLOR, UI_TEXT_COLOR},
+    mods::{ModPack, Mods},
+    resource::{GameAssets, Localization, Persistence, Settings, UiChannel},
+    systems::ui::{label, panel, text_button, UiClickEvent},
+};
+
+const ERROR_COLOR: Color = Color::rgb(0.9, 0.3, 0.3);
+
+fn toggle_text(localization: &Localization, settings: &Settings, pack: &ModPack) -> String {
+    let key = if settings.mod_enabled(&pack.id) {
+        "mods.on"
+    } else {
+        "mods.off"
+    };
+    localization.get(key).to_string()
+}
+
+// Packs only go in or out of the registries when the game starts
+fn restart_text(localization: &Localization, settings: &Settings, mods: &Mods) -> String {
+    let pending = mods
+        .packs
+        .iter()
+        .any(|pack| pack.enabled != settings.mod_enabled(&pack.id));
+    if pending {
+        localization.get("mods.restart").to_string()
+    } else {
+        String::new()
+    }
+}
+
+fn pack_layout(
+    asset_server: &Res<AssetServer>,
+    localization: &Localization,
+    parent: &mut ChildBuilder,
+    settings: &Settings,
+    (index, pack): (usize, &ModPack),
+) {
+    parent
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(90.0), Val::Auto),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+            ModsMenu,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Percent(100.0), Val::Px(60.0)),
+                            align_items: AlignItems::Center,
+                            justify_content: JustifyContent::SpaceBetween,
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    ModsMenu,
+                ))
+                .with_children(|parent| {
+                    label(
+                        parent,
+                        asset_server,
+                        ModsMenu,
+                        &format!("{} {}", pack.name, pack.version),
+                        30.0,
+                        UI_TEXT_COLOR,
+                    );
+                    text_button(
+                        parent,
+                        asset_server,
+                        ModsMenu,
+                        UiAction::ToggleMod(index),
+                        Size::new(Val::Px(200.0), Val::Px(50.0)),
+                        &toggle_text(localization, settings, pack),
+                        ModToggleText(index),
+                    );
+                });
+            if !pack.description.is_empty() {
+                label(
+                    parent,
+                    asset_server,
+                    ModsMenu,
+                    &pack.description,
+                    18.0,
+                    UI_TEXT_COLOR,
+                );
+            }
+            if !pack.errors.is_empty() {
+                label(
+                    parent,
+                    asset_server,
+                    ModsMenu,
+                    &format!(
+                        "{}\n{}",
+                        localization.get("mods.broken"),
+                        pack.errors.join("\n")
+                    ),
+                    16.0,
+                    ERROR_COLOR,
+                );
+            }
+        });
+}
+
+pub fn setup_mods_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mods: Res<Mods>,
+    settings: Res<Settings>,
+    localization: Res<Localization>,
+) {
+    commands.spawn((Camera2dBundle::default(), ModsMenu));
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            ModsMenu,
+            UiBackAction(UiAction::BackToMainMenu),
+        ))
+        .with_children(|parent| {
+            panel(
+                parent,
+                ModsMenu,
+                Style {
+                    size: Size::new(Val::Percent(70.0), Val::Percent(90.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::SpaceAround,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                UI_PANEL_COLOR,
+            )
+            .with_children(|parent| {
+                label(
+                    parent,
+                    &asset_server,
+                    ModsMenu,
+                    localization.get("mods.title"),
+                    40.0,
+                    UI_TEXT_COLOR,
+                )
+                .insert(LocalizedText("mods.title"));
+                if mods.packs.is_empty() {
+                    label(
+                        parent,
+                        &asset_server,
+                        ModsMenu,
+                        &localization.format("mods.empty", &[("folder", &MODS_FOLDER)]),
+                        20.0,
+                        UI_TEXT_COLOR,
+                    );
+                }
+                for pack in mods.packs.iter().enumerate() {
+                    pack_layout(&asset_server, &localization, parent, &settings, pack);
+                }
+                label(
+                    parent,
+                    &asset_server,
+                    ModsMenu,
+                    &restart_text(&localization, &settings, &mods),
+                    20.0,
+                    UI_TEXT_COLOR,
+                )
+                .insert(ModsRestartText);
+                text_button(
+                    parent,
+                    &asset_server,
+                    ModsMenu,
+                    UiAction::BackToMainMenu,
+                    Size::new(Val::Px(150.0), Val::Px(50.0)),
+                    localization.get("mods.back"),
+                    LocalizedText("mods.back"),
+                );
+            });
+        });
+}
+
+pub fn clean_mods_menu(
+    mut commands: Commands,
+    mods_menu_query: Query<Entity, With<ModsMenu>>,
+    settings: Res<Settings>,
+    persistence: Res<Persistence>,
+) {
+    for entity in mods_menu_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    if persistence.enabled {
+        settings.save();
+    }
+}
+
+// Pack toggles, and the restart notice when they hold no marker
+type ModsTexts<'w, 's> = Query<
+    'w,
+    's,
+    (&'static mut Text, Option<&'static ModToggleText>),
+    Or<(With<ModToggleText>, With<ModsRestartText>)>,
+>;
+
+// Enabling or disabling a pack only applies on the next launch
+pub fn mods_menu_button(
+    mut click_event: EventReader<UiClickEvent>,
+    mods: Res<Mods>,
+    mut settings: ResMut<Settings>,
+    localization: Res<Localization>,
+    mut query_text: ModsTexts,
+    assets: Res<GameAssets>,
+    ui_channel: Res<AudioChannel<UiChannel>>,
+) {
+    for event in click_event.iter() {
+        let UiAction::ToggleMod(index) = event.action else {
+            continue;
+        };
+        let Some(pack) = mods.packs.get(index) else {
+            continue;
+        };
+        if !settings.disabled_mods.remove(&pack.id) {
+            settings.disabled_mods.insert(pack.id.clone());
+        }
+        for (mut text, toggle) in &mut query_text {
+            match toggle {
+                Some(toggle) if toggle.0 == index => {
+                    text.sections[0].value = toggle_text(&localization, &settings, pack)
+                }
+                Some(_) => {}
+                None => text.sections[0].value = restart_text(&localization, &settings, &mods),
+            }
+        }
+        ui_channel.play(assets.hit_sound.clone());
+    }
+}
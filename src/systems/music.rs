@@ -0,0 +1,74 @@
+use bevy::prelude::{Assets, Handle, Query, Res, ResMut, State, With};
+use bevy_kira_audio::prelude::*;
+use std::time::Duration;
+
+use crate::{
+    components::Enemy,
+    constants::{MUSIC_CROSSFADE_SECONDS, MUSIC_INTENSITY_ENEMIES, MUSIC_MAX_TEMPO_BOOST},
+    resource::{GameAssets, MusicChannel, MusicDirector, Score},
+    AppState,
+};
+
+// Track expected for the current screen, menus share the theme and a run follows its historic period
+fn wanted_track(
+    app_state: &AppState,
+    score: &Score,
+    assets: &GameAssets,
+) -> Option<Handle<AudioSource>> {
+    match app_state {
+        AppState::MainMenu
+        | AppState::PreStartMenu
+        | AppState::SettingsMenu
+        | AppState::ModsMenu
+        | AppState::AchievementsMenu => Some(assets.theme_music.clone()),
+        AppState::InGame | AppState::LevelMenu | AppState::Paused => {
+            Some(assets.era_at(score.get_level_index()).music.clone())
+        }
+        AppState::Loading | AppState::RetryMenu => None,
+    }
+}
+
+// Faster playback as the live enemies pile up, back to normal outside of a wave
+fn wanted_playback_rate(enemies: usize) -> f64 {
+    let intensity = (enemies as f32 / MUSIC_INTENSITY_ENEMIES).min(1.0);
+    1.0 + (MUSIC_MAX_TEMPO_BOOST * intensity) as f64
+}
+
+pub fn music_director_update(
+    app_state: Res<State<AppState>>,
+    score: Res<Score>,
+    assets: Res<GameAssets>,
+    music_channel: Res<AudioChannel<MusicChannel>>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    mut director: ResMut<MusicDirector>,
+    query_enemies: Query<(), With<Enemy>>,
+) {
+    let crossfade = AudioTween::linear(Duration::from_secs_f32(MUSIC_CROSSFADE_SECONDS));
+    let track = wanted_track(&app_state.0, &score, &assets);
+    if track != director.track {
+        // Fade the old track out while the new one fades in, through the channel so a track
+        // still waiting to start is dropped too
+        music_channel.stop().fade_out(crossfade.clone());
+        director.instance = track.as_ref().map(|track| {
+            music_channel
+                .play(track.clone())
+                .looped()
+                .fade_in(crossfade.clone())
+                .handle()
+        });
+        director.track = track;
+        director.playback_rate = 1.0;
+    }
+
+    let playback_rate = wanted_playback_rate(query_enemies.iter().count());
+    if (playback_rate - director.playback_rate).abs() > 0.01 {
+        if let Some(instance) = director
+            .instance
+            .as_ref()
+            .and_then(|handle| audio_instances.get_mut(handle))
+        {
+            instance.set_playback_rate(playback_rate, crossfade);
+            director.playback_rate = playback_rate;
+        }
+    }
+}
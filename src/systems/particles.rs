@@ -0,0 +1,179 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    prelude::{Color, Commands, Entity, EventReader, Query, Res, Transform, Vec2, Vec3, With},
+    sprite::{Sprite, SpriteBundle},
+    time::Time,
+    utils::default,
+};
+use rand::Rng;
+
+use crate::{
+    components::{InGame, Particle},
+    constants::{PARTICLE_BUDGET, PARTICLE_DRAG, PARTICLE_Z},
+    resource::Settings,
+    systems::in_game::lerp_color,
+};
+
+#[derive(Clone, Copy)]
+pub enum ParticleEffect {
+    Hit,
+    Death,
+    MuzzleFlash,
+}
+
+pub struct ParticleEvent {
+    pub effect: ParticleEffect,
+    pub position: Vec3,
+    // Center of the spread, bursts with a zero direction are thrown all around
+    pub direction: Vec2,
+}
+
+// Burst of particles thrown by an effect
+pub struct ParticleEmitter {
+    pub count: u32,
+    pub lifetime: f32,
+    pub min_speed: f32,
+    pub max_speed: f32,
+    // Angle of the cone the particles are thrown in
+    pub spread: f32,
+    pub start_color: Color,
+    pub end_color: Color,
+    // Size at birth, half way through and at the end of the life
+    pub sizes: [f32; 3],
+}
+
+const HIT_EMITTER: ParticleEmitter = ParticleEmitter {
+    count: 3,
+    lifetime: 0.25,
+    min_speed: 80.0,
+    max_speed: 220.0,
+    spread: 1.2,
+    start_color: Color::rgb(1.0, 0.9, 0.5),
+    end_color: Color::rgba(1.0, 0.3, 0.0, 0.0),
+    sizes: [4.0, 3.0, 1.0],
+};
+
+const DEATH_EMITTER: ParticleEmitter = ParticleEmitter {
+    count: 12,
+    lifetime: 0.6,
+    min_speed: 40.0,
+    max_speed: 200.0,
+    spread: TAU,
+    start_color: Color::rgb(0.8, 0.8, 0.8),
+    end_color: Color::rgba(0.2, 0.2, 0.2, 0.0),
+    sizes: [6.0, 8.0, 2.0],
+};
+
+const MUZZLE_FLASH_EMITTER: ParticleEmitter = ParticleEmitter {
+    count: 4,
+    lifetime: 0.08,
+    min_speed: 300.0,
+    max_speed: 500.0,
+    spread: 0.5,
+    start_color: Color::rgb(1.0, 0.95, 0.6),
+    end_color: Color::rgba(1.0, 0.6, 0.2, 0.0),
+    sizes: [5.0, 4.0, 1.0],
+};
+
+impl ParticleEffect {
+    pub fn emitter(&self) -> &'static ParticleEmitter {
+        match self {
+            ParticleEffect::Hit => &HIT_EMITTER,
+            ParticleEffect::Death => &DEATH_EMITTER,
+            ParticleEffect::MuzzleFlash => &MUZZLE_FLASH_EMITTER,
+        }
+    }
+}
+
+impl Particle {
+    fn progress(&self) -> f32 {
+        (self.age / self.lifetime).min(1.0)
+    }
+
+    pub fn color(&self) -> Color {
+        lerp_color(self.start_color, self.end_color, self.progress())
+    }
+
+    pub fn size(&self) -> f32 {
+        let progress = self.progress() * 2.0;
+        let [start, middle, end] = self.sizes;
+        if progress < 1.0 {
+            start + (middle - start) * progress
+        } else {
+            middle + (end - middle) * (progress - 1.0)
+        }
+    }
+}
+
+// Bursts past the budget are cut short so the late waves keep their frame rate
+pub fn spawn_particles(
+    mut commands: Commands,
+    mut particle_events: EventReader<ParticleEvent>,
+    settings: Res<Settings>,
+    query: Query<(), With<Particle>>,
+) {
+    // Fewer and slower particles for players sensitive to motion
+    let (budget, motion) = if settings.accessibility.reduce_motion {
+        (PARTICLE_BUDGET / 2, 0.5)
+    } else {
+        (PARTICLE_BUDGET, 1.0)
+    };
+    let mut live = query.iter().count();
+    // Cosmetic only, seeded runs must not depend on it
+    let mut rng = rand::thread_rng();
+    for event in particle_events.iter() {
+        let emitter = event.effect.emitter();
+        let count =
+            ((emitter.count as f32 * motion).ceil() as usize).min(budget.saturating_sub(live));
+        let heading = event.direction.y.atan2(event.direction.x);
+        for _ in 0..count {
+            let angle = heading + rng.gen_range(-0.5..0.5) * emitter.spread;
+            let speed = rng.gen_range(emitter.min_speed..=emitter.max_speed) * motion;
+            let particle = Particle {
+                age: 0.0,
+                lifetime: emitter.lifetime,
+                velocity: Vec2::new(angle.cos(), angle.sin()) * speed,
+                start_color: emitter.start_color,
+                end_color: emitter.end_color,
+                sizes: emitter.sizes,
+            };
+            commands.spawn((
+                SpriteBundle {
+                    transform: Transform::from_translation(
+                        event.position.truncate().extend(PARTICLE_Z),
+                    ),
+                    sprite: Sprite {
+                        color: particle.color(),
+                        custom_size: Some(Vec2::splat(particle.size())),
+                        ..default()
+                    },
+                    ..default()
+                },
+                particle,
+                InGame,
+            ));
+        }
+        live += count;
+    }
+}
+
+pub fn particle_update(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    let delta = time.delta_seconds();
+    let drag = (1.0 - PARTICLE_DRAG * delta).max(0.0);
+    query.for_each_mut(|(entity, mut particle, mut transform, mut sprite)| {
+        particle.age += delta;
+        if particle.age >= particle.lifetime {
+            commands.entity(entity).despawn();
+            return;
+        }
+        transform.translation += (particle.velocity * delta).extend(0.0);
+        particle.velocity *= drag;
+        sprite.color = particle.color();
+        sprite.custom_size = Some(Vec2::splat(particle.size()));
+    });
+}
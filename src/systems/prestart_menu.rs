@@ -1,25 +1,28 @@
-use crate::components::PreStartMenu;
-use crate::{constants::LORE_INTRO, AppState};
-use bevy::prelude::{ImageBundle, NextState};
+use crate::components::{PreStartMenu, UiAction, UiBackAction};
+use crate::constants::{UI_PANEL_COLOR, UI_TEXT_COLOR};
+use crate::systems::ui::{label, panel, text_button, UiClickEvent};
+use crate::{
+    resource::{calendar_date, DailyRun, GameAssets, Localization},
+    AppState,
+};
+use bevy::prelude::{EventReader, ImageBundle, NextState};
 use bevy::ui::{PositionType, UiImage, UiRect};
 use bevy::{
     prelude::{
-        AssetServer, BuildChildren, Button, ButtonBundle, Camera2dBundle, Changed, Children, Color,
-        Commands, Entity, NodeBundle, Query, Res, ResMut, TextBundle, With,
-    },
-    text::TextStyle,
-    ui::{
-        AlignItems, BackgroundColor, FlexDirection, Interaction, JustifyContent, Size, Style, Val,
+        AssetServer, BuildChildren, Camera2dBundle, Commands, Entity, NodeBundle, Query, Res,
+        ResMut, With,
     },
+    ui::{AlignItems, FlexDirection, JustifyContent, Size, Style, Val},
     utils::default,
 };
-use bevy_kira_audio::prelude::*;
-
-// UI
-const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
-const BACKGROUND_COLOR_UI: Color = Color::rgb(0.65, 0.65, 0.65);
 
-pub fn setup_pre_start_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub fn setup_pre_start_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    assets: Res<GameAssets>,
+    localization: Res<Localization>,
+    daily: Option<Res<DailyRun>>,
+) {
     commands.spawn((Camera2dBundle::default(), PreStartMenu));
     commands
         .spawn((
@@ -33,6 +36,7 @@ pub fn setup_pre_start_menu(mut commands: Commands, asset_server: Res<AssetServe
                 ..default()
             },
             PreStartMenu,
+            UiBackAction(UiAction::BackToMainMenu),
         ))
         .with_children(|parent| {
             parent.spawn((
@@ -44,99 +48,80 @@ pub fn setup_pre_start_menu(mut commands: Commands, asset_server: Res<AssetServe
                         position_type: PositionType::Absolute,
                         ..default()
                     },
-                    image: UiImage::new(asset_server.load("images/title_screen.png")),
+                    image: UiImage::new(assets.title_screen.clone()),
                     ..default()
                 },
                 PreStartMenu,
             ));
-            parent
-                .spawn((
-                    NodeBundle {
-                        style: Style {
-                            size: Size::new(Val::Percent(50.0), Val::Percent(40.0)),
-                            align_items: AlignItems::Center,
-                            margin: UiRect {
-                                top: Val::Percent(10.0),
-                                ..default()
-                            },
-                            justify_content: JustifyContent::SpaceAround,
-                            flex_direction: FlexDirection::Column,
-                            ..default()
-                        },
-                        background_color: NORMAL_BUTTON.into(),
+            panel(
+                parent,
+                PreStartMenu,
+                Style {
+                    size: Size::new(Val::Percent(50.0), Val::Percent(40.0)),
+                    align_items: AlignItems::Center,
+                    margin: UiRect {
+                        top: Val::Percent(10.0),
                         ..default()
                     },
-                    PreStartMenu,
-                ))
-                .with_children(|parent| {
-                    parent.spawn((
-                        TextBundle::from_section(
-                            LORE_INTRO,
-                            TextStyle {
-                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                                font_size: 18.0,
-                                color: Color::rgb(0.9, 0.9, 0.9),
-                            },
-                        ),
+                    justify_content: JustifyContent::SpaceAround,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                UI_PANEL_COLOR,
+            )
+            .with_children(|parent| {
+                if let Some(daily) = &daily {
+                    let mut text = localization
+                        .format("prestart.daily", &[("day", &calendar_date(daily.day))]);
+                    if !daily.scored {
+                        text = text + "\n" + localization.get("prestart.daily_practice");
+                    }
+                    label(
+                        parent,
+                        &asset_server,
                         PreStartMenu,
-                    ));
-                    parent
-                        .spawn((
-                            ButtonBundle {
-                                style: Style {
-                                    size: Size::new(Val::Px(170.0), Val::Px(65.0)),
-                                    // horizontally center child text
-                                    justify_content: JustifyContent::Center,
-                                    // vertically center child text
-                                    align_items: AlignItems::Center,
-                                    ..default()
-                                },
-                                background_color: BACKGROUND_COLOR_UI.into(),
-                                ..default()
-                            },
-                            PreStartMenu,
-                        ))
-                        .with_children(|parent| {
-                            parent.spawn((
-                                TextBundle::from_section(
-                                    "KILL THEM",
-                                    TextStyle {
-                                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                                        font_size: 32.0,
-                                        color: NORMAL_BUTTON,
-                                    },
-                                ),
-                                PreStartMenu,
-                            ));
-                        });
-                });
+                        &text,
+                        24.0,
+                        UI_TEXT_COLOR,
+                    );
+                }
+                label(
+                    parent,
+                    &asset_server,
+                    PreStartMenu,
+                    localization.get("lore.intro"),
+                    18.0,
+                    UI_TEXT_COLOR,
+                );
+                text_button(
+                    parent,
+                    &asset_server,
+                    PreStartMenu,
+                    UiAction::EnterGame,
+                    Size::new(Val::Px(170.0), Val::Px(65.0)),
+                    localization.get("prestart.kill_them"),
+                    (),
+                );
+            });
         });
 }
 
 pub fn clean_pre_start_menu(
     mut commands: Commands,
     pre_start_menu_query: Query<Entity, With<PreStartMenu>>,
-    audio: Res<Audio>,
 ) {
     for entity in pre_start_menu_query.iter() {
         commands.entity(entity).despawn();
     }
-    audio.stop();
 }
 
 pub fn ingame_button(
+    mut click_event: EventReader<UiClickEvent>,
     mut app_state: ResMut<NextState<AppState>>,
-    mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor, &Children),
-        (Changed<Interaction>, With<Button>),
-    >,
 ) {
-    for (interaction, _, _) in &mut interaction_query {
-        match *interaction {
-            Interaction::Clicked => {
-                app_state.set(AppState::InGame);
-            }
-            _ => {}
+    for event in click_event.iter() {
+        if let UiAction::EnterGame = event.action {
+            app_state.set(AppState::InGame);
         }
     }
 }
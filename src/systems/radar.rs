@@ -0,0 +1,357 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::{
+        AssetServer, BuildChildren, Commands, DetectChangesMut, IVec2, Mut, NodeBundle,
+        OrthographicProjection, Quat, Query, Res, ResMut, TextBundle, Transform, Vec2, Visibility,
+        With, Without,
+    },
+    text::Text,
+    time::Time,
+    ui::{Overflow, PositionType, Size, Style, UiRect, Val},
+    utils::default,
+    window::Window,
+};
+
+use crate::{
+    components::{
+        Downed, Enemy, EnemyIndicator, EnemyIndicatorArrow, EnemyIndicatorText, InGame, MainCamera,
+        MinimapDot, MinimapLayer, Player,
+    },
+    constants::{
+        INDICATOR_ARROW_SIZE, INDICATOR_MARGIN, INDICATOR_SECTORS, MINIMAP_BACKGROUND_COLOR,
+        MINIMAP_CELLS, MINIMAP_CELL_WORLD, MINIMAP_CHUNK_COLOR, MINIMAP_FULL_CLUSTER, MINIMAP_SIZE,
+        UI_TEXT_COLOR,
+    },
+    resource::{ChunksMap, Party, RadarRefresh, Settings},
+    systems::ui::text_style,
+};
+
+// Chunks around the minimap center, one more than the cells so the edges stay covered
+const MINIMAP_CHUNKS: usize = (MINIMAP_CELLS + 1) * (MINIMAP_CELLS + 1);
+
+pub fn setup_radar(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+    party: Res<Party>,
+) {
+    let hurt_color = settings.accessibility.palette.hurt_color();
+    for sector in 0..INDICATOR_SECTORS {
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        size: Size::new(Val::Px(0.0), Val::Px(0.0)),
+                        ..default()
+                    },
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                EnemyIndicator(sector),
+                InGame,
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            position: UiRect {
+                                left: Val::Px(-INDICATOR_ARROW_SIZE.x / 2.0),
+                                top: Val::Px(-INDICATOR_ARROW_SIZE.y / 2.0),
+                                ..default()
+                            },
+                            size: Size::new(
+                                Val::Px(INDICATOR_ARROW_SIZE.x),
+                                Val::Px(INDICATOR_ARROW_SIZE.y),
+                            ),
+                            ..default()
+                        },
+                        background_color: hurt_color.into(),
+                        ..default()
+                    },
+                    EnemyIndicatorArrow(sector),
+                    InGame,
+                ));
+                parent.spawn((
+                    TextBundle::from_section("", text_style(&asset_server, 18.0, UI_TEXT_COLOR))
+                        .with_style(Style {
+                            position_type: PositionType::Absolute,
+                            ..default()
+                        }),
+                    EnemyIndicatorText(sector),
+                    InGame,
+                ));
+            });
+    }
+
+    if !settings.hud.minimap {
+        return;
+    }
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        right: Val::Px(10.0),
+                        top: Val::Px(10.0),
+                        ..default()
+                    },
+                    size: Size::new(Val::Px(MINIMAP_SIZE), Val::Px(MINIMAP_SIZE)),
+                    overflow: Overflow::Hidden,
+                    ..default()
+                },
+                background_color: MINIMAP_BACKGROUND_COLOR.into(),
+                ..default()
+            },
+            InGame,
+        ))
+        .with_children(|parent| {
+            // Spawned in drawing order, chunks under the enemies under the players
+            let layers = [
+                (MinimapLayer::Chunk, MINIMAP_CHUNKS, MINIMAP_CHUNK_COLOR),
+                (
+                    MinimapLayer::Enemies,
+                    MINIMAP_CELLS * MINIMAP_CELLS,
+                    hurt_color,
+                ),
+                (MinimapLayer::Player, party.count(), UI_TEXT_COLOR),
+            ];
+            for (layer, count, color) in layers {
+                for index in 0..count {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                ..default()
+                            },
+                            background_color: color.into(),
+                            visibility: Visibility::Hidden,
+                            ..default()
+                        },
+                        MinimapDot { layer, index },
+                        InGame,
+                    ));
+                }
+            }
+        });
+}
+
+pub fn radar_refresh_tick(time: Res<Time>, mut refresh: ResMut<RadarRefresh>) {
+    refresh.tick(time.delta());
+}
+
+// Absolute node placement, only written when it moves so the layout is not redone for nothing
+fn move_to(style: &mut Mut<Style>, position: Vec2) {
+    let (left, top) = (Val::Px(position.x), Val::Px(position.y));
+    if style.position.left != left || style.position.top != top {
+        style.position.left = left;
+        style.position.top = top;
+    }
+}
+
+fn place(style: &mut Mut<Style>, position: Vec2, size: Vec2) {
+    move_to(style, position);
+    let size = Size::new(Val::Px(size.x), Val::Px(size.y));
+    if style.size != size {
+        style.size = size;
+    }
+}
+
+// Keeps the indicator transforms apart from the world ones read alongside
+type UiOnly = (Without<Enemy>, Without<MainCamera>);
+
+#[derive(SystemParam)]
+pub struct IndicatorNodes<'w, 's> {
+    indicators: Query<
+        'w,
+        's,
+        (
+            &'static EnemyIndicator,
+            &'static mut Style,
+            &'static mut Visibility,
+        ),
+        Without<EnemyIndicatorText>,
+    >,
+    arrows: Query<'w, 's, (&'static EnemyIndicatorArrow, &'static mut Transform), UiOnly>,
+    texts: Query<
+        'w,
+        's,
+        (
+            &'static EnemyIndicatorText,
+            &'static mut Text,
+            &'static mut Style,
+        ),
+    >,
+}
+
+// Group the enemies outside the view by direction and point at every group from the screen edge
+pub fn enemy_indicator_update(
+    refresh: Res<RadarRefresh>,
+    windows: Query<&Window>,
+    query_camera: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+    query_enemy: Query<&Transform, With<Enemy>>,
+    mut nodes: IndicatorNodes,
+) {
+    if !refresh.just_finished() {
+        return;
+    }
+    let (Ok(window), Ok((camera_transform, projection))) =
+        (windows.get_single(), query_camera.get_single())
+    else {
+        return;
+    };
+    let center = camera_transform.translation.truncate();
+    let area = projection.area;
+    let mut sectors = [(0_u32, Vec2::ZERO); INDICATOR_SECTORS];
+    query_enemy.for_each(|transform| {
+        let offset = transform.translation.truncate() - center;
+        if offset.cmpge(area.min).all() && offset.cmple(area.max).all() {
+            return;
+        }
+        let angle = offset.y.atan2(offset.x) + PI;
+        let sector = ((angle / TAU * INDICATOR_SECTORS as f32) as usize) % INDICATOR_SECTORS;
+        sectors[sector].0 += 1;
+        sectors[sector].1 += offset.normalize_or_zero();
+    });
+
+    let half_screen = Vec2::new(window.width(), window.height()) / 2.0 - INDICATOR_MARGIN;
+    let mut edge_points = [None; INDICATOR_SECTORS];
+    for (sector, (count, direction)) in sectors.iter().enumerate() {
+        let Some(direction) = direction.try_normalize() else {
+            continue;
+        };
+        // UI coordinates grow downwards
+        let screen_direction = Vec2::new(direction.x, -direction.y);
+        let reach = (half_screen / screen_direction.abs()).min_element();
+        edge_points[sector] = Some((
+            half_screen + INDICATOR_MARGIN + screen_direction * reach,
+            screen_direction,
+            *count,
+        ));
+    }
+    for (indicator, mut style, mut visibility) in &mut nodes.indicators {
+        match edge_points[indicator.0] {
+            Some((position, _, _)) => {
+                move_to(&mut style, position);
+                visibility.set_if_neq(Visibility::Inherited);
+            }
+            None => {
+                visibility.set_if_neq(Visibility::Hidden);
+            }
+        }
+    }
+    for (arrow, mut transform) in &mut nodes.arrows {
+        if let Some((_, screen_direction, _)) = edge_points[arrow.0] {
+            // Measured in UI space like the direction, where y points down
+            let rotation = Quat::from_rotation_z(screen_direction.y.atan2(screen_direction.x));
+            transform.set_if_neq(Transform {
+                rotation,
+                ..*transform
+            });
+        }
+    }
+    for (text, mut content, mut style) in &mut nodes.texts {
+        if let Some((_, screen_direction, count)) = edge_points[text.0] {
+            let value = count.to_string();
+            if content.sections[0].value != value {
+                content.sections[0].value = value;
+            }
+            // Count written on the inner side of the arrow, roughly centered on its glyphs
+            let inward = -screen_direction * INDICATOR_ARROW_SIZE.x - Vec2::new(8.0, 10.0);
+            move_to(&mut style, inward);
+        }
+    }
+}
+
+// Loaded chunks, enemy clusters and players around the camera, binned on the minimap grid
+pub fn minimap_update(
+    refresh: Res<RadarRefresh>,
+    chunks: Res<ChunksMap>,
+    query_camera: Query<&Transform, With<MainCamera>>,
+    query_enemy: Query<&Transform, With<Enemy>>,
+    query_player: Query<(&Transform, Option<&Downed>), With<Player>>,
+    mut query_dot: Query<(&MinimapDot, &mut Style, &mut Visibility)>,
+) {
+    if !refresh.just_finished() || query_dot.is_empty() {
+        return;
+    }
+    let Ok(camera_transform) = query_camera.get_single() else {
+        return;
+    };
+    let center = camera_transform.translation.truncate();
+    let span = MINIMAP_CELLS as f32 * MINIMAP_CELL_WORLD;
+    let cell_size = MINIMAP_SIZE / MINIMAP_CELLS as f32;
+    // From the world to the minimap, where y points down
+    let to_map = |position: Vec2| {
+        let offset = (position - center) / span;
+        Vec2::new(0.5 + offset.x, 0.5 - offset.y) * MINIMAP_SIZE
+    };
+
+    let mut chunk_dots = Vec::with_capacity(MINIMAP_CHUNKS);
+    let first_chunk = (center / MINIMAP_CELL_WORLD).round().as_ivec2() - MINIMAP_CELLS as i32 / 2;
+    for i in 0..=MINIMAP_CELLS as i32 {
+        for j in 0..=MINIMAP_CELLS as i32 {
+            let chunk = first_chunk + IVec2::new(i, j);
+            if chunks.chunks.contains_key(&(chunk.x, chunk.y)) {
+                // Chunk sprites are centered on their grid point
+                let corner = chunk.as_vec2() * MINIMAP_CELL_WORLD
+                    + Vec2::new(-0.5, 0.5) * MINIMAP_CELL_WORLD;
+                chunk_dots.push((to_map(corner), Vec2::splat(cell_size)));
+            }
+        }
+    }
+
+    let mut clusters = [0_u32; MINIMAP_CELLS * MINIMAP_CELLS];
+    query_enemy.for_each(|transform| {
+        let cell = (to_map(transform.translation.truncate()) / cell_size).floor();
+        if cell.cmpge(Vec2::ZERO).all() && cell.cmplt(Vec2::splat(MINIMAP_CELLS as f32)).all() {
+            clusters[cell.y as usize * MINIMAP_CELLS + cell.x as usize] += 1;
+        }
+    });
+    let enemy_dots: Vec<(Vec2, Vec2)> = clusters
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .map(|(cell, count)| {
+            let size = cell_size * (0.3 + 0.7 * (*count as f32 / MINIMAP_FULL_CLUSTER).min(1.0));
+            let cell_center = Vec2::new(
+                (cell % MINIMAP_CELLS) as f32 + 0.5,
+                (cell / MINIMAP_CELLS) as f32 + 0.5,
+            ) * cell_size;
+            (cell_center - size / 2.0, Vec2::splat(size))
+        })
+        .collect();
+
+    // Downed players are drawn smaller
+    let player_dots: Vec<(Vec2, Vec2)> = query_player
+        .iter()
+        .map(|(transform, downed)| {
+            let size = if downed.is_some() { 4.0 } else { 6.0 };
+            (
+                to_map(transform.translation.truncate()) - size / 2.0,
+                Vec2::splat(size),
+            )
+        })
+        .collect();
+
+    for (dot, mut style, mut visibility) in &mut query_dot {
+        let dots = match dot.layer {
+            MinimapLayer::Chunk => &chunk_dots,
+            MinimapLayer::Enemies => &enemy_dots,
+            MinimapLayer::Player => &player_dots,
+        };
+        match dots.get(dot.index) {
+            Some((position, size)) => {
+                place(&mut style, *position, *size);
+                visibility.set_if_neq(Visibility::Inherited);
+            }
+            None => {
+                visibility.set_if_neq(Visibility::Hidden);
+            }
+        }
+    }
+}
@@ -1,38 +1,81 @@
 use bevy::{
+    ecs::system::SystemParam,
     prelude::{
-        AssetServer, BuildChildren, Button, ButtonBundle, Camera2dBundle, Changed, Children, Color,
-        Commands, Entity, NextState, NodeBundle, Query, Res, ResMut, TextBundle, With,
-    },
-    text::TextStyle,
-    ui::{
-        AlignItems, BackgroundColor, FlexDirection, Interaction, JustifyContent, Size, Style, Val,
+        AssetServer, BuildChildren, Camera2dBundle, Color, Commands, Entity, EventReader,
+        NextState, NodeBundle, Query, Res, ResMut, With,
     },
+    ui::{AlignItems, FlexDirection, JustifyContent, Size, Style, Val},
     utils::default,
 };
-use bevy_kira_audio::prelude::*;
 
 use crate::{
-    components::RetryMenu,
-    resource::{TotalKilled, TotalSpawned, TotalToSpawn},
-    StatsRes,
+    components::{RetryMenu, UiAction, UiBackAction},
+    constants::UI_TEXT_COLOR,
+    resource::{HighScoreEntry, HighScores, Localization, Party, Persistence, StoryProgress},
+    systems::{
+        daily::DailyBoard,
+        in_game::WaveTotals,
+        ui::{label, text_button, UiClickEvent},
+    },
 };
 use crate::{resource::Score, AppState};
 
-// UI
-const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+fn high_score_text(localization: &Localization, rank: usize, entry: &HighScoreEntry) -> String {
+    localization.format(
+        "retry.high_score",
+        &[
+            ("rank", &rank),
+            ("date", &entry.date),
+            ("wave", &(entry.level + 1)),
+            ("difficulty", &localization.get(entry.difficulty.key())),
+        ],
+    )
+}
+
+// What a run leaves behind once it is over
+#[derive(SystemParam)]
+pub struct RunRecords<'w> {
+    high_scores: ResMut<'w, HighScores>,
+    story_progress: ResMut<'w, StoryProgress>,
+    persistence: Res<'w, Persistence>,
+}
 
 pub fn setup_retry_menu(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mut total_to_spawn: ResMut<TotalToSpawn>,
-    mut total_killed: ResMut<TotalKilled>,
-    mut total_spawned: ResMut<TotalSpawned>,
+    mut totals: WaveTotals,
     score: Res<Score>,
+    mut records: RunRecords,
+    localization: Res<Localization>,
+    mut daily: DailyBoard,
 ) {
+    let scored = daily.record(&score, &records.persistence);
+    if !daily.is_daily() && score.full_run() {
+        records.high_scores.insert(HighScoreEntry {
+            date: score.get_date(),
+            level: score.get_level_index(),
+            difficulty: score.difficulty(),
+        });
+    }
+    // Bot and demo runs are not the player's deaths
+    if records.persistence.enabled {
+        records.high_scores.save();
+        records.story_progress.deaths += 1;
+        records.story_progress.save();
+    }
+    let scores_text = if daily.is_daily() {
+        daily.text(&localization, scored)
+    } else {
+        records
+            .high_scores
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| high_score_text(&localization, i + 1, entry) + "\n")
+            .collect::<String>()
+    };
     // ui camera
-    total_to_spawn.update_paramter_for_level_id(0);
-    total_killed.amount = 0;
-    total_spawned.amount = 0;
+    totals.start(0, score.difficulty());
     commands.spawn((Camera2dBundle::default(), RetryMenu));
     commands
         .spawn((
@@ -47,90 +90,65 @@ pub fn setup_retry_menu(
                 ..default()
             },
             RetryMenu,
+            UiBackAction(UiAction::BackToMainMenu),
         ))
         .with_children(|parent| {
-            parent.spawn((
-                TextBundle::from_section(
-                    "GAME OVER",
-                    TextStyle {
-                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                        font_size: 40.0,
-                        color: Color::rgb(0.9, 0.9, 0.9),
-                    },
-                ),
+            label(
+                parent,
+                &asset_server,
+                RetryMenu,
+                localization.get("retry.game_over"),
+                40.0,
+                UI_TEXT_COLOR,
+            );
+            label(
+                parent,
+                &asset_server,
+                RetryMenu,
+                &localization.format("retry.killed_in", &[("year", &score.to_text())]),
+                40.0,
+                Color::rgb(0.9, 0.0, 0.0),
+            );
+            label(
+                parent,
+                &asset_server,
                 RetryMenu,
-            ));
-            parent.spawn((
-                TextBundle::from_section(
-                    format!("Your ancestor was killed in the year {}", score.to_text()),
-                    TextStyle {
-                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                        font_size: 40.0,
-                        color: Color::rgb(0.9, 0.0, 0.0),
-                    },
-                ),
+                &scores_text,
+                24.0,
+                Color::rgb(0.9, 0.8, 0.3),
+            );
+            text_button(
+                parent,
+                &asset_server,
                 RetryMenu,
-            ));
-            parent
-                .spawn((
-                    ButtonBundle {
-                        style: Style {
-                            size: Size::new(Val::Px(150.0), Val::Px(65.0)),
-                            // horizontally center child text
-                            justify_content: JustifyContent::Center,
-                            // vertically center child text
-                            align_items: AlignItems::Center,
-                            ..default()
-                        },
-                        background_color: NORMAL_BUTTON.into(),
-                        ..default()
-                    },
-                    RetryMenu,
-                ))
-                .with_children(|parent| {
-                    parent.spawn((
-                        TextBundle::from_section(
-                            "Retry",
-                            TextStyle {
-                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                                font_size: 40.0,
-                                color: Color::rgb(0.9, 0.9, 0.9),
-                            },
-                        ),
-                        RetryMenu,
-                    ));
-                });
+                UiAction::Retry,
+                Size::new(Val::Px(150.0), Val::Px(65.0)),
+                localization.get("retry.retry"),
+                (),
+            );
         });
 }
 
 pub fn clean_retry_menu(
     mut commands: Commands,
     retry_menu_query: Query<Entity, With<RetryMenu>>,
-    mut stats: ResMut<StatsRes>,
+    mut party: ResMut<Party>,
     mut score: ResMut<Score>,
-    audio: Res<Audio>,
 ) {
     for entity in retry_menu_query.iter() {
         commands.entity(entity).despawn();
     }
-    *stats = StatsRes::default();
-    *score = Score::default();
-    audio.stop();
+    party.reset();
+    *score = Score::new(score.difficulty());
 }
 
 pub fn retry_button(
+    mut click_event: EventReader<UiClickEvent>,
     mut app_state: ResMut<NextState<AppState>>,
-    mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor, &Children),
-        (Changed<Interaction>, With<Button>),
-    >,
 ) {
-    for (interaction, _, _) in &mut interaction_query {
-        match *interaction {
-            Interaction::Clicked => {
-                app_state.set(AppState::InGame);
-            }
-            _ => {}
+    for event in click_event.iter() {
+        if let UiAction::Retry = event.action {
+            app_state.set(AppState::InGame);
         }
     }
 }
@@ -0,0 +1,174 @@
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::{Commands, EventReader, Local, Query, Res, ResMut, Transform, Vec2, Vec3, With},
+    time::Time,
+};
+use rand::Rng;
+use rhai::FuncArgs;
+
+use crate::{
+    components::{Downed, Harm, Move, Player, PlayerSlot, Weapon},
+    constants::{PLAYER_POSITION, SCRIPT_RELOAD_SECONDS},
+    resource::{GameAssets, GameRng, Party, Score, TotalSpawned, TotalToSpawn},
+    scripting::{PlayerView, ScriptCommand, ScriptView, Scripts},
+    systems::in_game::{spawn_enemy, EnemyKilledEvent, WaveDoneEvent},
+};
+
+type ScriptPlayer = (
+    &'static PlayerSlot,
+    &'static Transform,
+    Option<&'static Downed>,
+    &'static mut Move,
+    &'static mut Harm,
+    &'static mut Weapon,
+);
+
+// What the scripts of the wave read, and what their commands change
+#[derive(SystemParam)]
+pub struct ScriptGame<'w, 's> {
+    commands: Commands<'w, 's>,
+    scripts: ResMut<'w, Scripts>,
+    party: ResMut<'w, Party>,
+    score: Res<'w, Score>,
+    assets: Res<'w, GameAssets>,
+    rng: ResMut<'w, GameRng>,
+    to_spawn: ResMut<'w, TotalToSpawn>,
+    spawned: ResMut<'w, TotalSpawned>,
+    players: Query<'w, 's, ScriptPlayer, With<Player>>,
+}
+
+impl ScriptGame<'_, '_> {
+    fn active(&self) -> Vec<usize> {
+        self.scripts
+            .active(self.score.get_level_index(), &self.party.flaws)
+    }
+
+    fn view(&self) -> ScriptView {
+        let mut players: Vec<PlayerView> = self
+            .party
+            .players
+            .iter()
+            .map(|stats| PlayerView::new(PLAYER_POSITION.truncate(), true, stats))
+            .collect();
+        for (slot, transform, downed, ..) in self.players.iter() {
+            if let Some(view) = players.get_mut(slot.0) {
+                view.position = transform.translation.truncate();
+                view.alive = downed.is_none();
+            }
+        }
+        ScriptView {
+            wave: self.score.get_level_index() + 1,
+            year: self.score.get_date(),
+            players,
+        }
+    }
+
+    fn run(&mut self, handler: &str, args: impl FuncArgs + Clone) {
+        let active = self.active();
+        if active.is_empty() {
+            return;
+        }
+        let view = self.view();
+        for command in self.scripts.call(&active, view, handler, args) {
+            match command {
+                ScriptCommand::Spawn {
+                    position,
+                    robot,
+                    elite,
+                } => self.spawn(position, robot, elite),
+                ScriptCommand::SetStat {
+                    player,
+                    stat,
+                    value,
+                } => {
+                    let stats = &mut self.party.players[player];
+                    stat.set(stats, value);
+                    // Health only counts from the next wave, the rest changes on the field right away
+                    for (slot, _, _, mut movable, mut harm, mut weapon) in &mut self.players {
+                        if slot.0 == player {
+                            movable.speed = stats.player_speed;
+                            harm.damage = stats.player_damage;
+                            weapon.fire_rate = stats.player_fire_rate;
+                            weapon.bullets = stats.player_bullets;
+                            weapon.bullet_ttl = stats.player_bullets_ttl;
+                            weapon.bullet_speed = stats.player_bullets_speed;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Script enemies come on top of the wave, it only ends once they are down too
+    fn spawn(&mut self, position: Vec2, robot: bool, elite: bool) {
+        let roster = self
+            .assets
+            .era_at(self.score.get_level_index())
+            .roster(robot);
+        let (kind, texture_atlas) = match roster.len() {
+            1 => &roster[0],
+            len => &roster[self.rng.gen_range(0..len)],
+        };
+        let target = self
+            .players
+            .iter()
+            .filter(|(_, _, downed, ..)| downed.is_none())
+            .map(|(_, transform, ..)| transform.translation)
+            .min_by(|a, b| {
+                let distance = |target: &Vec3| target.truncate().distance(position);
+                distance(a).total_cmp(&distance(b))
+            })
+            .unwrap_or(PLAYER_POSITION);
+        spawn_enemy(
+            &mut self.commands,
+            kind,
+            texture_atlas,
+            position.extend(target.z),
+            target,
+            elite,
+            &self.score.difficulty().modifiers(),
+        );
+        self.to_spawn.amount += 1;
+        self.spawned.amount += 1;
+    }
+}
+
+// Scripts draw from their own rng, seeded from the run one only when a wave has any
+pub fn script_wave_start(mut game: ScriptGame) {
+    if game.active().is_empty() {
+        return;
+    }
+    let seed = game.rng.gen();
+    game.scripts.reseed(seed);
+    let wave = game.score.get_level_index() + 1;
+    game.run("on_wave_start", (wave as i64,));
+}
+
+pub fn script_tick(
+    time: Res<Time>,
+    mut kill_events: EventReader<EnemyKilledEvent>,
+    mut game: ScriptGame,
+) {
+    for kill in kill_events.iter() {
+        let (x, y) = (kill.position.x as f64, kill.position.y as f64);
+        game.run("on_kill", (x, y, kill.elite));
+    }
+    game.run("on_tick", (time.delta_seconds() as f64,));
+}
+
+pub fn script_wave_done(mut wave_done_event: EventReader<WaveDoneEvent>, mut game: ScriptGame) {
+    if !wave_done_event.is_empty() {
+        wave_done_event.clear();
+        let wave = game.score.get_level_index() + 1;
+        game.run("on_wave_done", (wave as i64,));
+    }
+}
+
+// Edited script files are picked up without restarting the game
+pub fn reload_scripts(time: Res<Time>, mut since: Local<f32>, mut scripts: ResMut<Scripts>) {
+    *since += time.delta_seconds();
+    if *since >= SCRIPT_RELOAD_SECONDS {
+        *since = 0.0;
+        scripts.reload_changed();
+    }
+}
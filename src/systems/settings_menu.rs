@@ -0,0 +1,420 @@
+use bevy::{
+    prelude::{
+        AssetServer, BuildChildren, Bundle, Camera2dBundle, ChildBuilder, Commands, DetectChanges,
+        Entity, EventReader, NextState, NodeBundle, ParamSet, Query, Res, ResMut, With,
+    },
+    text::Text,
+    ui::{AlignItems, FlexDirection, JustifyContent, Size, Style, Val},
+    utils::default,
+};
+use bevy_kira_audio::prelude::*;
+
+use crate::{
+    components::{
+        LocalizedText, SettingsAction, SettingsMenu, SettingsOption, SettingsOptionText,
+        SettingsValueText, UiAction, UiBackAction,
+    },
+    constants::{UI_PANEL_COLOR, UI_TEXT_COLOR, VOLUME_STEP},
+    resource::{
+        ChannelSettings, GameAssets, Localization, MusicChannel, Persistence, Settings,
+        SettingsChannel, SfxChannel, UiChannel,
+    },
+    systems::ui::{label, panel, text_button, UiClickEvent},
+    AppState,
+};
+
+fn channel_label(channel: SettingsChannel) -> &'static str {
+    match channel {
+        SettingsChannel::Music => "settings.music",
+        SettingsChannel::Sfx => "settings.effects",
+        SettingsChannel::Ui => "settings.interface",
+    }
+}
+
+fn channel_text(localization: &Localization, channel_settings: &ChannelSettings) -> String {
+    if channel_settings.muted {
+        localization.get("settings.muted").to_string()
+    } else {
+        format!("{}%", (channel_settings.volume * 100.0).round() as i32)
+    }
+}
+
+fn option_text(localization: &Localization, settings: &Settings, option: SettingsOption) -> String {
+    let accessibility = &settings.accessibility;
+    let on_off = |on| localization.get(if on { "settings.on" } else { "settings.off" });
+    match option {
+        SettingsOption::Language => localization.format(
+            "settings.language",
+            &[("language", &localization.language_name(settings.language))],
+        ),
+        SettingsOption::Minimap => localization.format(
+            "settings.minimap",
+            &[("state", &on_off(settings.hud.minimap))],
+        ),
+        SettingsOption::DamageNumbers => localization.format(
+            "settings.damage_numbers",
+            &[("state", &on_off(settings.hud.damage_numbers))],
+        ),
+        SettingsOption::Palette => localization.format(
+            "settings.palette",
+            &[("palette", &localization.get(accessibility.palette.key()))],
+        ),
+        SettingsOption::TextScale => localization.format(
+            "settings.text_scale",
+            &[("percent", &(accessibility.text_scale * 100.0).round())],
+        ),
+        SettingsOption::ReduceMotion => localization.format(
+            "settings.reduce_motion",
+            &[("state", &on_off(accessibility.reduce_motion))],
+        ),
+        SettingsOption::FireMode => localization.format(
+            "settings.fire_mode",
+            &[("mode", &localization.get(accessibility.fire_mode.key()))],
+        ),
+    }
+}
+
+fn cycle_option(settings: &mut Settings, option: SettingsOption) {
+    let accessibility = &mut settings.accessibility;
+    match option {
+        SettingsOption::Language => settings.language = settings.language.next(),
+        SettingsOption::Minimap => settings.hud.minimap = !settings.hud.minimap,
+        SettingsOption::DamageNumbers => settings.hud.damage_numbers = !settings.hud.damage_numbers,
+        SettingsOption::Palette => accessibility.palette = accessibility.palette.next(),
+        SettingsOption::TextScale => accessibility.next_text_scale(),
+        SettingsOption::ReduceMotion => accessibility.reduce_motion = !accessibility.reduce_motion,
+        SettingsOption::FireMode => accessibility.fire_mode = accessibility.fire_mode.next(),
+    }
+}
+
+fn option_button_layout(
+    asset_server: &Res<AssetServer>,
+    localization: &Localization,
+    parent: &mut ChildBuilder,
+    settings: &Settings,
+    option: SettingsOption,
+) {
+    settings_button_layout(
+        asset_server,
+        parent,
+        &option_text(localization, settings, option),
+        SettingsOptionText(option),
+        SettingsAction::Cycle(option),
+        380.0,
+    );
+}
+
+fn options_row_layout(
+    asset_server: &Res<AssetServer>,
+    localization: &Localization,
+    parent: &mut ChildBuilder,
+    settings: &Settings,
+    options: [SettingsOption; 2],
+) {
+    parent
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(90.0), Val::Px(60.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::SpaceAround,
+                    ..default()
+                },
+                ..default()
+            },
+            SettingsMenu,
+        ))
+        .with_children(|parent| {
+            for option in options {
+                option_button_layout(asset_server, localization, parent, settings, option);
+            }
+        });
+}
+
+// `text_marker` tags the text so it can be rewritten when the language changes
+fn settings_button_layout(
+    asset_server: &Res<AssetServer>,
+    parent: &mut ChildBuilder,
+    text: &str,
+    text_marker: impl Bundle,
+    action: SettingsAction,
+    width: f32,
+) {
+    text_button(
+        parent,
+        asset_server,
+        SettingsMenu,
+        UiAction::Settings(action),
+        Size::new(Val::Px(width), Val::Px(50.0)),
+        text,
+        text_marker,
+    );
+}
+
+fn channel_row_layout(
+    asset_server: &Res<AssetServer>,
+    localization: &Localization,
+    parent: &mut ChildBuilder,
+    channel: SettingsChannel,
+    channel_settings: &ChannelSettings,
+) {
+    parent
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(90.0), Val::Px(60.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::SpaceBetween,
+                    ..default()
+                },
+                ..default()
+            },
+            SettingsMenu,
+        ))
+        .with_children(|parent| {
+            label(
+                parent,
+                asset_server,
+                SettingsMenu,
+                localization.get(channel_label(channel)),
+                30.0,
+                UI_TEXT_COLOR,
+            )
+            .insert(LocalizedText(channel_label(channel)));
+            settings_button_layout(
+                asset_server,
+                parent,
+                localization.get("settings.volume_down"),
+                LocalizedText("settings.volume_down"),
+                SettingsAction::VolumeDown(channel),
+                50.0,
+            );
+            label(
+                parent,
+                asset_server,
+                SettingsMenu,
+                &channel_text(localization, channel_settings),
+                30.0,
+                UI_TEXT_COLOR,
+            )
+            .insert(SettingsValueText(channel));
+            settings_button_layout(
+                asset_server,
+                parent,
+                localization.get("settings.volume_up"),
+                LocalizedText("settings.volume_up"),
+                SettingsAction::VolumeUp(channel),
+                50.0,
+            );
+            settings_button_layout(
+                asset_server,
+                parent,
+                localization.get("settings.mute"),
+                LocalizedText("settings.mute"),
+                SettingsAction::ToggleMute(channel),
+                100.0,
+            );
+        });
+}
+
+pub fn setup_settings_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+    localization: Res<Localization>,
+) {
+    commands.spawn((Camera2dBundle::default(), SettingsMenu));
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            SettingsMenu,
+            UiBackAction(UiAction::Settings(SettingsAction::Back)),
+        ))
+        .with_children(|parent| {
+            panel(
+                parent,
+                SettingsMenu,
+                Style {
+                    size: Size::new(Val::Percent(70.0), Val::Percent(90.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::SpaceAround,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                UI_PANEL_COLOR,
+            )
+            .with_children(|parent| {
+                label(
+                    parent,
+                    &asset_server,
+                    SettingsMenu,
+                    localization.get("settings.title"),
+                    40.0,
+                    UI_TEXT_COLOR,
+                )
+                .insert(LocalizedText("settings.title"));
+                for channel in [
+                    SettingsChannel::Music,
+                    SettingsChannel::Sfx,
+                    SettingsChannel::Ui,
+                ] {
+                    channel_row_layout(
+                        &asset_server,
+                        &localization,
+                        parent,
+                        channel,
+                        settings.audio.channel(channel),
+                    );
+                }
+                option_button_layout(
+                    &asset_server,
+                    &localization,
+                    parent,
+                    &settings,
+                    SettingsOption::Language,
+                );
+                options_row_layout(
+                    &asset_server,
+                    &localization,
+                    parent,
+                    &settings,
+                    [SettingsOption::Minimap, SettingsOption::DamageNumbers],
+                );
+                label(
+                    parent,
+                    &asset_server,
+                    SettingsMenu,
+                    localization.get("settings.accessibility"),
+                    30.0,
+                    UI_TEXT_COLOR,
+                )
+                .insert(LocalizedText("settings.accessibility"));
+                options_row_layout(
+                    &asset_server,
+                    &localization,
+                    parent,
+                    &settings,
+                    [SettingsOption::Palette, SettingsOption::TextScale],
+                );
+                options_row_layout(
+                    &asset_server,
+                    &localization,
+                    parent,
+                    &settings,
+                    [SettingsOption::ReduceMotion, SettingsOption::FireMode],
+                );
+                settings_button_layout(
+                    &asset_server,
+                    parent,
+                    localization.get("settings.back"),
+                    LocalizedText("settings.back"),
+                    SettingsAction::Back,
+                    150.0,
+                );
+            });
+        });
+}
+
+pub fn clean_settings_menu(
+    mut commands: Commands,
+    settings_menu_query: Query<Entity, With<SettingsMenu>>,
+    settings: Res<Settings>,
+    persistence: Res<Persistence>,
+) {
+    for entity in settings_menu_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    if persistence.enabled {
+        settings.save();
+    }
+}
+
+// Volume values and option names, both rewritten whenever the settings change
+type SettingsTexts<'w, 's> = ParamSet<
+    'w,
+    's,
+    (
+        Query<'static, 'static, (&'static mut Text, &'static SettingsValueText)>,
+        Query<'static, 'static, (&'static mut Text, &'static SettingsOptionText)>,
+    ),
+>;
+
+pub fn settings_button(
+    mut app_state: ResMut<NextState<AppState>>,
+    mut settings: ResMut<Settings>,
+    mut localization: ResMut<Localization>,
+    mut click_event: EventReader<UiClickEvent>,
+    mut query_text: SettingsTexts,
+    assets: Res<GameAssets>,
+    ui_channel: Res<AudioChannel<UiChannel>>,
+) {
+    for event in click_event.iter() {
+        let UiAction::Settings(action) = event.action else {
+            continue;
+        };
+        match action {
+            SettingsAction::VolumeUp(channel) => {
+                settings.audio.channel_mut(channel).step_volume(VOLUME_STEP)
+            }
+            SettingsAction::VolumeDown(channel) => settings
+                .audio
+                .channel_mut(channel)
+                .step_volume(-VOLUME_STEP),
+            SettingsAction::ToggleMute(channel) => {
+                let channel_settings = settings.audio.channel_mut(channel);
+                channel_settings.muted = !channel_settings.muted;
+            }
+            SettingsAction::Cycle(option) => {
+                cycle_option(&mut settings, option);
+                localization.language = settings.language;
+            }
+            SettingsAction::Back => app_state.set(AppState::MainMenu),
+        }
+        ui_channel.play(assets.hit_sound.clone());
+    }
+    if !settings.is_changed() {
+        return;
+    }
+    for (mut text, value_text) in &mut query_text.p0() {
+        text.sections[0].value = channel_text(&localization, settings.audio.channel(value_text.0));
+    }
+    for (mut text, option_text_marker) in &mut query_text.p1() {
+        text.sections[0].value = option_text(&localization, &settings, option_text_marker.0);
+    }
+}
+
+// Rewrite every single string text in the newly picked language
+pub fn update_localized_texts(
+    localization: Res<Localization>,
+    mut query_text: Query<(&mut Text, &LocalizedText)>,
+) {
+    if !localization.is_changed() {
+        return;
+    }
+    for (mut text, localized_text) in &mut query_text {
+        text.sections[0].value = localization.get(localized_text.0).to_string();
+    }
+}
+
+// Push the saved volumes to every channel whenever the settings change
+pub fn apply_audio_settings(
+    settings: Res<Settings>,
+    music_channel: Res<AudioChannel<MusicChannel>>,
+    sfx_channel: Res<AudioChannel<SfxChannel>>,
+    ui_channel: Res<AudioChannel<UiChannel>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    music_channel.set_volume(settings.audio.music.effective_volume());
+    sfx_channel.set_volume(settings.audio.sfx.effective_volume());
+    ui_channel.set_volume(settings.audio.ui.effective_volume());
+}
@@ -0,0 +1,387 @@
+use bevy::{
+    ecs::system::{EntityCommands, SystemParam},
+    prelude::{
+        AssetServer, Axis, BuildChildren, Bundle, ButtonBundle, Changed, ChildBuilder, Color,
+        Commands, Component, DetectChanges, Entity, EventReader, EventWriter, GamepadAxis,
+        GamepadAxisType, GamepadButton, GamepadButtonType, Gamepads, GlobalTransform, Input,
+        KeyCode, Local, NextState, NodeBundle, Query, Res, ResMut, TextBundle, Vec2, With, Without,
+    },
+    text::{Text, TextStyle},
+    time::Time,
+    ui::{AlignItems, BackgroundColor, Interaction, JustifyContent, Size, Style, Val},
+    utils::default,
+};
+
+use crate::{
+    components::{BaseFontSize, Focused, SlideIn, UiAction, UiBackAction, UiButton},
+    constants::{
+        UI_BUTTON_COLOR, UI_BUTTON_DISABLED_COLOR, UI_BUTTON_FOCUS_COLOR, UI_BUTTON_FONT_SIZE,
+        UI_BUTTON_HOVER_COLOR, UI_BUTTON_PRESSED_COLOR, UI_FONT, UI_SLIDE_SPEED,
+        UI_STICK_THRESHOLD, UI_TEXT_COLOR,
+    },
+    resource::Settings,
+    AppState,
+};
+
+pub struct UiClickEvent {
+    pub entity: Entity,
+    pub action: UiAction,
+}
+
+pub fn text_style(asset_server: &AssetServer, font_size: f32, color: Color) -> TextStyle {
+    TextStyle {
+        font: asset_server.load(UI_FONT),
+        font_size,
+        color,
+    }
+}
+
+pub fn label<'w, 's, 'a>(
+    parent: &'a mut ChildBuilder<'w, 's, '_>,
+    asset_server: &AssetServer,
+    menu: impl Component,
+    text: &str,
+    font_size: f32,
+    color: Color,
+) -> EntityCommands<'w, 's, 'a> {
+    parent.spawn((
+        TextBundle::from_section(text, text_style(asset_server, font_size, color)),
+        menu,
+    ))
+}
+
+pub fn panel<'w, 's, 'a>(
+    parent: &'a mut ChildBuilder<'w, 's, '_>,
+    menu: impl Component,
+    style: Style,
+    color: Color,
+) -> EntityCommands<'w, 's, 'a> {
+    parent.spawn((
+        NodeBundle {
+            style,
+            background_color: color.into(),
+            ..default()
+        },
+        menu,
+    ))
+}
+
+// A panel starting at the top offset of its style and sliding down into place
+pub fn slide_in_panel<'w, 's, 'a>(
+    parent: &'a mut ChildBuilder<'w, 's, '_>,
+    menu: impl Component,
+    style: Style,
+    color: Color,
+) -> EntityCommands<'w, 's, 'a> {
+    let mut panel = panel(parent, menu, style, color);
+    panel.insert(SlideIn {
+        speed: UI_SLIDE_SPEED,
+    });
+    panel
+}
+
+// An empty button centering its content, filled by the caller
+pub fn button<'w, 's, 'a>(
+    parent: &'a mut ChildBuilder<'w, 's, '_>,
+    menu: impl Component,
+    action: UiAction,
+    style: Style,
+) -> EntityCommands<'w, 's, 'a> {
+    parent.spawn((
+        ButtonBundle {
+            style: Style {
+                // horizontally center child text
+                justify_content: JustifyContent::Center,
+                // vertically center child text
+                align_items: AlignItems::Center,
+                ..style
+            },
+            background_color: UI_BUTTON_COLOR.into(),
+            ..default()
+        },
+        menu,
+        UiButton {
+            action,
+            disabled: false,
+        },
+    ))
+}
+
+// `text_marker` tags the text so it can be rewritten later
+pub fn text_button<'w, 's, 'a, M: Component + Copy>(
+    parent: &'a mut ChildBuilder<'w, 's, '_>,
+    asset_server: &AssetServer,
+    menu: M,
+    action: UiAction,
+    size: Size,
+    text: &str,
+    text_marker: impl Bundle,
+) -> EntityCommands<'w, 's, 'a> {
+    let mut button = button(parent, menu, action, Style { size, ..default() });
+    button.with_children(|parent| {
+        label(
+            parent,
+            asset_server,
+            menu,
+            text,
+            UI_BUTTON_FONT_SIZE,
+            UI_TEXT_COLOR,
+        )
+        .insert(text_marker);
+    });
+    button
+}
+
+pub fn button_states(
+    mut query_button: Query<(
+        &Interaction,
+        &UiButton,
+        Option<&Focused>,
+        &mut BackgroundColor,
+    )>,
+) {
+    for (interaction, button, focused, mut color) in &mut query_button {
+        let state_color = match (button.disabled, *interaction, focused.is_some()) {
+            (true, _, _) => UI_BUTTON_DISABLED_COLOR,
+            (false, Interaction::Clicked, _) => UI_BUTTON_PRESSED_COLOR,
+            (false, _, true) => UI_BUTTON_FOCUS_COLOR,
+            (false, Interaction::Hovered, false) => UI_BUTTON_HOVER_COLOR,
+            (false, Interaction::None, false) => UI_BUTTON_COLOR,
+        };
+        if color.0 != state_color {
+            color.0 = state_color;
+        }
+    }
+}
+
+pub fn button_clicks(
+    query_button: Query<(Entity, &Interaction, &UiButton), Changed<Interaction>>,
+    mut click_event: EventWriter<UiClickEvent>,
+) {
+    for (entity, interaction, button) in &query_button {
+        if *interaction == Interaction::Clicked && !button.disabled {
+            click_event.send(UiClickEvent {
+                entity,
+                action: button.action,
+            });
+        }
+    }
+}
+
+#[derive(SystemParam)]
+pub struct MenuInput<'w, 's> {
+    keys: Res<'w, Input<KeyCode>>,
+    gamepad_buttons: Res<'w, Input<GamepadButton>>,
+    gamepads: Res<'w, Gamepads>,
+    axes: Res<'w, Axis<GamepadAxis>>,
+    // The stick moves the focus once per push, not on every frame it is held
+    last_stick: Local<'s, Vec2>,
+}
+
+impl MenuInput<'_, '_> {
+    fn just_pressed(&self, keys: &[KeyCode], button_type: GamepadButtonType) -> bool {
+        self.keys.any_just_pressed(keys.iter().copied())
+            || self
+                .gamepad_buttons
+                .get_just_pressed()
+                .any(|button| button.button_type == button_type)
+    }
+
+    fn stick(&self) -> Vec2 {
+        let axis = |gamepad, axis_type| {
+            self.axes
+                .get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or(0.0)
+        };
+        self.gamepads
+            .iter()
+            .map(|gamepad| {
+                Vec2::new(
+                    axis(gamepad, GamepadAxisType::LeftStickX),
+                    -axis(gamepad, GamepadAxisType::LeftStickY),
+                )
+            })
+            .find(|stick| stick.length() > UI_STICK_THRESHOLD)
+            .map(|stick| {
+                if stick.x.abs() > stick.y.abs() {
+                    Vec2::new(stick.x.signum(), 0.0)
+                } else {
+                    Vec2::new(0.0, stick.y.signum())
+                }
+            })
+            .unwrap_or(Vec2::ZERO)
+    }
+
+    // Where to move the focus, y pointing down like the UI layout
+    pub fn direction(&mut self) -> Option<Vec2> {
+        let stick = self.stick();
+        let pushed = stick != *self.last_stick;
+        *self.last_stick = stick;
+        if pushed && stick != Vec2::ZERO {
+            return Some(stick);
+        }
+        [
+            (KeyCode::Up, GamepadButtonType::DPadUp, Vec2::NEG_Y),
+            (KeyCode::Down, GamepadButtonType::DPadDown, Vec2::Y),
+            (KeyCode::Left, GamepadButtonType::DPadLeft, Vec2::NEG_X),
+            (KeyCode::Right, GamepadButtonType::DPadRight, Vec2::X),
+        ]
+        .into_iter()
+        .find(|(key, button_type, _)| self.just_pressed(&[*key], *button_type))
+        .map(|(_, _, direction)| direction)
+    }
+
+    pub fn activate(&self) -> bool {
+        self.just_pressed(&[KeyCode::Return, KeyCode::Space], GamepadButtonType::South)
+    }
+
+    pub fn back(&self) -> bool {
+        self.just_pressed(&[KeyCode::Escape], GamepadButtonType::East)
+    }
+}
+
+// The closest enabled button in that direction, favouring the ones right in line
+fn next_focus(
+    from: Vec2,
+    direction: Vec2,
+    query_button: &Query<(Entity, &UiButton, &GlobalTransform)>,
+) -> Option<Entity> {
+    query_button
+        .iter()
+        .filter(|(_, button, _)| !button.disabled)
+        .filter_map(|(entity, _, transform)| {
+            let offset = transform.translation().truncate() - from;
+            let along = offset.dot(direction);
+            let across = offset.perp_dot(direction).abs();
+            (along > 1.0).then_some((entity, along + 2.0 * across))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity)
+}
+
+// The top left enabled button, focused on the first key press of a menu
+fn first_focus(query_button: &Query<(Entity, &UiButton, &GlobalTransform)>) -> Option<Entity> {
+    query_button
+        .iter()
+        .filter(|(_, button, _)| !button.disabled)
+        .min_by(|(_, _, a), (_, _, b)| {
+            let (a, b) = (a.translation(), b.translation());
+            a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))
+        })
+        .map(|(entity, _, _)| entity)
+}
+
+// Buttons the mouse just moved over or off
+type HoveredButtons<'w, 's> =
+    Query<'w, 's, (Entity, &'static Interaction), (Changed<Interaction>, With<UiButton>)>;
+
+pub fn menu_navigation(
+    mut commands: Commands,
+    mut input: MenuInput,
+    query_button: Query<(Entity, &UiButton, &GlobalTransform)>,
+    query_hovered: HoveredButtons,
+    query_focused: Query<Entity, With<Focused>>,
+    query_back: Query<(Entity, &UiBackAction)>,
+    mut click_event: EventWriter<UiClickEvent>,
+) {
+    let focused = query_focused.get_single().ok();
+    // The mouse moves the focus too so a single button is ever highlighted
+    let mut target = query_hovered
+        .iter()
+        .find(|(_, interaction)| **interaction == Interaction::Hovered)
+        .map(|(entity, _)| entity);
+    if let Some(direction) = input.direction() {
+        target = match focused.and_then(|entity| query_button.get(entity).ok()) {
+            Some((_, _, transform)) => {
+                next_focus(transform.translation().truncate(), direction, &query_button)
+            }
+            None => first_focus(&query_button),
+        };
+    }
+    if let Some(target) = target.filter(|target| Some(*target) != focused) {
+        if let Some(focused) = focused {
+            commands.entity(focused).remove::<Focused>();
+        }
+        commands.entity(target).insert(Focused);
+    }
+    if input.activate() {
+        if let Some((entity, button, _)) = focused.and_then(|entity| query_button.get(entity).ok())
+        {
+            if !button.disabled {
+                click_event.send(UiClickEvent {
+                    entity,
+                    action: button.action,
+                });
+            }
+        }
+    }
+    if input.back() {
+        for (entity, back_action) in &query_back {
+            click_event.send(UiClickEvent {
+                entity,
+                action: back_action.0,
+            });
+        }
+    }
+}
+
+pub fn back_to_main_menu(
+    mut click_event: EventReader<UiClickEvent>,
+    mut app_state: ResMut<NextState<AppState>>,
+) {
+    for event in click_event.iter() {
+        if let UiAction::BackToMainMenu = event.action {
+            app_state.set(AppState::MainMenu);
+        }
+    }
+}
+
+pub fn slide_in_update(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut query_panel: Query<(&mut Style, &SlideIn)>,
+) {
+    for (mut style, slide_in) in &mut query_panel {
+        if let Val::Percent(y) = style.position.top {
+            let new_value = y + slide_in.speed * time.delta_seconds();
+            if new_value > 0.0 || settings.accessibility.reduce_motion {
+                style.position.top = Val::Undefined;
+            } else {
+                style.position.top = Val::Percent(new_value);
+            }
+        }
+    }
+}
+
+// Scale every text, the ones just spawned and all of them when the setting changes. World text
+// like damage numbers goes through here too, so every text is spawned at its unscaled size
+pub fn apply_text_scale(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut query_new_text: Query<(Entity, &mut Text), Without<BaseFontSize>>,
+    mut query_text: Query<(&mut Text, &BaseFontSize)>,
+) {
+    let scale = settings.accessibility.text_scale;
+    for (entity, mut text) in &mut query_new_text {
+        let base = BaseFontSize(
+            text.sections
+                .iter()
+                .map(|section| section.style.font_size)
+                .collect(),
+        );
+        scale_text(&mut text, &base, scale);
+        commands.entity(entity).insert(base);
+    }
+    if !settings.is_changed() {
+        return;
+    }
+    for (mut text, base) in &mut query_text {
+        scale_text(&mut text, base, scale);
+    }
+}
+
+fn scale_text(text: &mut Text, base: &BaseFontSize, scale: f32) {
+    for (section, font_size) in text.sections.iter_mut().zip(&base.0) {
+        section.style.font_size = font_size * scale;
+    }
+}